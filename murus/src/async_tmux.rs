@@ -0,0 +1,136 @@
+//! Non-blocking tmux API for applications running on the tokio runtime. Available with the
+//! `async` feature.
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    CommandOutcome, Error, OptionScope, Socket, default_binary, format_cmd, get_option_args,
+    option_value, session, session::Session, set_option_args, tmux_command,
+};
+
+/// Async counterpart of [`crate::Tmux`], running tmux commands through
+/// [`tokio::process::Command`] so they don't block the event loop.
+pub struct AsyncTmux {
+    timeout: Option<Duration>,
+    socket: Option<Socket>,
+    binary: PathBuf,
+}
+
+impl AsyncTmux {
+    pub fn try_new() -> Result<Self, Error> {
+        if crate::Tmux::is_inside() {
+            Ok(Self {
+                timeout: None,
+                socket: crate::Tmux::socket_path().map(Socket::Path),
+                binary: default_binary(),
+            })
+        } else {
+            Err(Error::NotInTmux)
+        }
+    }
+
+    /// See [`crate::Tmux::with_binary`].
+    pub fn with_binary(mut self, binary: PathBuf) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    /// See [`crate::Tmux::with_socket_name`].
+    pub fn with_socket_name(mut self, name: &str) -> Self {
+        self.socket = Some(Socket::Name(name.to_string()));
+        self
+    }
+
+    /// See [`crate::Tmux::with_socket_path`].
+    pub fn with_socket_path(mut self, path: &Path) -> Self {
+        self.socket = Some(Socket::Path(path.to_path_buf()));
+        self
+    }
+
+    /// See [`crate::Tmux::with_timeout`].
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// See [`crate::Tmux::get_option`].
+    pub async fn get_option(&self, option: &str, scope: OptionScope) -> Result<String, Error> {
+        let outcome = self
+            .run_capture(get_option_args(option, scope, None))
+            .await?;
+
+        option_value(outcome)
+    }
+
+    /// See [`crate::Tmux::set_option`].
+    pub async fn set_option(
+        &self,
+        option: &str,
+        value: &str,
+        scope: OptionScope,
+    ) -> Result<(), Error> {
+        self.run_capture(set_option_args(option, value, scope, None, false))
+            .await?
+            .into_result()?;
+
+        Ok(())
+    }
+
+    /// See [`crate::Tmux::list_sessions`].
+    pub async fn list_sessions(&self) -> Result<Vec<Session>, Error> {
+        let outcome = self
+            .run_capture(["list-sessions", "-F", session::LIST_SESSIONS_FORMAT])
+            .await?
+            .into_result()?;
+
+        let sessions = outcome
+            .stdout
+            .lines()
+            .filter_map(Session::from_formatted)
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// See [`crate::Tmux::switch_session`].
+    pub async fn switch_session(&self, session: &Session) -> Result<(), Error> {
+        self.run_capture(["switch-client", "-t", &session.name])
+            .await?
+            .into_result()?;
+
+        Ok(())
+    }
+
+    /// Runs tmux with the given arguments and captures its output streams and exit status.
+    async fn run_capture<I, S>(&self, args: I) -> Result<CommandOutcome, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut cmd =
+            tokio::process::Command::from(tmux_command(&self.binary, self.socket.as_ref()));
+        cmd.args(args);
+
+        let command = format_cmd(cmd.as_std());
+        log::debug!("{command}");
+
+        // NOTE: dropping the output future on timeout kills tmux
+        cmd.kill_on_drop(true);
+
+        let output = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, cmd.output())
+                .await
+                .map_err(|_| Error::Timeout(timeout))?,
+            None => cmd.output().await,
+        };
+
+        match output {
+            Ok(output) => Ok(CommandOutcome::new(command, output)),
+            Err(source) => Err(Error::CommandFailed { command, source }),
+        }
+    }
+}
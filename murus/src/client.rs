@@ -0,0 +1,60 @@
+/// Format passed to `tmux list-clients -F`, fields separated by the ASCII unit separator. Parsed
+/// by [`Client::from_formatted`].
+pub(crate) const LIST_CLIENTS_FORMAT: &str =
+    "#{client_tty}\x1f#{client_session}\x1f#{client_width}\x1f#{client_height}";
+
+/// Represents information about a single client attached to tmux.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Client {
+    /// Terminal device of the client, e.g. `/dev/pts/3`.
+    pub tty: String,
+    /// Name of the session the client is attached to.
+    pub session: String,
+    /// Size of the client as `(width, height)`.
+    pub size: (u16, u16),
+}
+
+impl Client {
+    /// Parses a single line of `tmux list-clients -F` output produced with
+    /// [`LIST_CLIENTS_FORMAT`]. Returns `None` if the line is malformed.
+    pub(crate) fn from_formatted(line: &str) -> Option<Self> {
+        let mut fields = line.split('\x1f');
+
+        let tty = fields.next()?.to_string();
+        let session = fields.next()?.to_string();
+        let width = fields.next()?.parse().ok()?;
+        let height = fields.next()?.parse().ok()?;
+
+        Some(Self {
+            tty,
+            session,
+            size: (width, height),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_formatted_parses_client() {
+        assert_eq!(
+            Client::from_formatted("/dev/pts/3\x1fmain\x1f120\x1f40"),
+            Some(Client {
+                tty: "/dev/pts/3".to_string(),
+                session: "main".to_string(),
+                size: (120, 40),
+            })
+        );
+    }
+
+    #[test]
+    fn from_formatted_rejects_malformed_lines() {
+        assert_eq!(Client::from_formatted(""), None);
+        assert_eq!(
+            Client::from_formatted("/dev/pts/3\x1fmain\x1fwide\x1f40"),
+            None
+        );
+    }
+}
@@ -0,0 +1,140 @@
+/// Represents a single key binding, as listed by `tmux list-keys`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    /// Key table the binding belongs to, e.g. `prefix` or `root`.
+    pub table: String,
+    /// Key, with tmux's quoting removed, e.g. `C-b` or `"`.
+    pub key: String,
+    /// Command run by the key, exactly as printed by tmux (i.e. still quoted).
+    pub command: String,
+}
+
+impl KeyBinding {
+    /// Parses a single line of `tmux list-keys` output, e.g.
+    /// `bind-key -r -T prefix Up select-pane -U`. Returns `None` if the line is malformed.
+    pub(crate) fn from_list_keys(line: &str) -> Option<Self> {
+        let (command, mut rest) = next_word(line)?;
+        if command != "bind-key" {
+            return None;
+        }
+
+        let mut table = String::from("prefix");
+
+        let key = loop {
+            let (word, remaining) = next_word(rest)?;
+            rest = remaining;
+
+            match word.as_str() {
+                "-T" => {
+                    let (name, remaining) = next_word(rest)?;
+                    table = name;
+                    rest = remaining;
+                }
+                // NOTE: other flags (e.g. -r for repeatable keys) don't take an argument
+                flag if flag.len() > 1 && flag.starts_with('-') => {}
+                _ => break word,
+            }
+        };
+
+        Some(Self {
+            table,
+            key,
+            command: rest.trim().to_string(),
+        })
+    }
+}
+
+/// Splits off the first word of `input`, removing tmux's quoting and escaping from it, and
+/// returns it along with the rest of the input.
+fn next_word(input: &str) -> Option<(String, &str)> {
+    let input = input.trim_start();
+    if input.is_empty() {
+        return None;
+    }
+
+    let mut word = String::new();
+    let mut quote = None;
+    let mut chars = input.char_indices();
+
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => return Some((word, &input[index..])),
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            // NOTE: there is no escaping inside single quotes
+            (Some('\''), c) => word.push(c),
+            (_, '\\') => word.extend(chars.next().map(|(_, escaped)| escaped)),
+            (_, c) => word.push(c),
+        }
+    }
+
+    Some((word, ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(table: &str, key: &str, command: &str) -> KeyBinding {
+        KeyBinding {
+            table: table.to_string(),
+            key: key.to_string(),
+            command: command.to_string(),
+        }
+    }
+
+    #[test]
+    fn from_list_keys_parses_bindings() {
+        let cases = [
+            (
+                "bind-key    -T copy-mode    C-Space               send-keys -X begin-selection",
+                binding("copy-mode", "C-Space", "send-keys -X begin-selection"),
+            ),
+            (
+                "bind-key -r -T prefix       Up                    select-pane -U",
+                binding("prefix", "Up", "select-pane -U"),
+            ),
+            (
+                r#"bind-key    -T prefix       \"                    split-window"#,
+                binding("prefix", "\"", "split-window"),
+            ),
+            (
+                r"bind-key    -T prefix       \;                    last-pane",
+                binding("prefix", ";", "last-pane"),
+            ),
+            (
+                r"bind-key    -T prefix       \#                    list-buffers",
+                binding("prefix", "#", "list-buffers"),
+            ),
+            (
+                "bind-key    -T prefix       '~'                   show-messages",
+                binding("prefix", "~", "show-messages"),
+            ),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(KeyBinding::from_list_keys(line), Some(expected), "{line}");
+        }
+    }
+
+    #[test]
+    fn from_list_keys_keeps_quoted_command() {
+        let line = r##"bind-key    -T root         MouseDown3Pane        if-shell -F -t = "#{mouse_any_flag}" { select-pane -t = ; send-keys -M } { display-menu -T "#[align=centre]#{pane_index}" }"##;
+
+        assert_eq!(
+            KeyBinding::from_list_keys(line),
+            Some(binding(
+                "root",
+                "MouseDown3Pane",
+                r##"if-shell -F -t = "#{mouse_any_flag}" { select-pane -t = ; send-keys -M } { display-menu -T "#[align=centre]#{pane_index}" }"##
+            ))
+        );
+    }
+
+    #[test]
+    fn from_list_keys_rejects_other_lines() {
+        assert_eq!(KeyBinding::from_list_keys(""), None);
+        assert_eq!(KeyBinding::from_list_keys("unbind-key -T prefix C-b"), None);
+        assert_eq!(KeyBinding::from_list_keys("bind-key -T prefix"), None);
+    }
+}
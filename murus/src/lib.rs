@@ -1,16 +1,31 @@
 //! `murus` is implementation of tmux API in Rust.
 
 use std::{
-    io,
-    path::Path,
-    process::{Command, Output},
+    collections::HashMap,
+    ffi::OsStr,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Output, Stdio},
+    sync::OnceLock,
+    time::{Duration, Instant},
 };
 
+use client::Client;
+use keys::KeyBinding;
+use pane::Pane;
 use session::Session;
+use window::{Layout, Window};
 
+#[cfg(feature = "async")]
+pub mod async_tmux;
+pub mod client;
+pub mod keys;
+pub mod pane;
 pub mod session;
+pub mod window;
 
-#[cfg(debug_assertions)]
+/// Formats the command line (program and arguments) of the command, used in error and debug
+/// messages.
 fn format_cmd(cmd: &Command) -> String {
     let mut output: String = cmd.get_program().to_string_lossy().to_string();
 
@@ -27,140 +42,1054 @@ pub enum Error {
     #[error("We are currently not in a tmux instance")]
     NotInTmux,
 
-    #[error("Command failed {}", .0)]
-    CommandFailed(#[from] io::Error),
+    #[error("Command '{command}' failed: {source}")]
+    CommandFailed {
+        command: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("No tmux server is running")]
+    NoServer,
 
     #[error("Option not found {}", .0)]
     OptionNotFound(String),
 
-    #[error("Failed sourcing file. stdout:\n{stdout}\n\nstderr:\n{stderr}")]
-    SourceFile { stdout: String, stderr: String },
+    #[error("Invalid value '{value}' for option {option}")]
+    OptionParse { option: String, value: String },
+
+    #[error("Session already exists: {}", .0)]
+    SessionExists(String),
+
+    #[error("Target not found: {}", .0)]
+    TargetNotFound(String),
+
+    #[error("tmux {required_major}.{required_minor} or newer is required, found {major}.{minor}")]
+    UnsupportedVersion {
+        major: u32,
+        minor: u32,
+        required_major: u32,
+        required_minor: u32,
+    },
+
+    #[error("Could not parse tmux version from '{}'", .0)]
+    VersionParse(String),
+
+    #[error("tmux command timed out after {:?}", .0)]
+    Timeout(Duration),
+
+    /// tmux ran, but exited unsuccessfully. `code` is `None` if tmux was terminated by a signal.
+    #[error("Command '{command}' failed ({}): {stderr}", describe_exit_code(*.code))]
+    Tmux {
+        command: String,
+        stderr: String,
+        code: Option<i32>,
+    },
+
+    /// tmux succeeded, but printed something other than the requested format.
+    #[error("Unexpected output of command '{command}': {output}")]
+    UnexpectedOutput { command: String, output: String },
+
+    #[error("Command '{command}' failed. stdout:\n{stdout}\n\nstderr:\n{stderr}")]
+    SourceFile {
+        command: String,
+        stdout: String,
+        stderr: String,
+    },
 }
 
-pub struct Tmux {}
+impl Error {
+    /// Whether the error is likely caused by tmux being temporarily busy (e.g. while clients
+    /// reattach) rather than by an invalid command or configuration, so retrying might succeed.
+    pub fn is_transient(&self) -> bool {
+        // NOTE: a busy server lock surfaces as EAGAIN, i.e. "resource temporarily unavailable"
+        const TRANSIENT_PATTERNS: [&str; 3] = [
+            "server exited",
+            "lost server",
+            "resource temporarily unavailable",
+        ];
+
+        let is_transient_message = |message: &str| {
+            let message = message.to_lowercase();
+            TRANSIENT_PATTERNS
+                .iter()
+                .any(|pattern| message.contains(pattern))
+        };
+
+        match self {
+            Error::CommandFailed { source, .. } => matches!(
+                source.kind(),
+                io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock
+            ),
+            Error::Tmux { stderr, .. } => is_transient_message(stderr),
+            // tmux prints errors in the sourced file to stdout
+            Error::SourceFile { stdout, stderr, .. } => {
+                is_transient_message(stdout) || is_transient_message(stderr)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Captured result of a finished tmux command.
+struct CommandOutcome {
+    command: String,
+    stdout: String,
+    stderr: String,
+    status: ExitStatus,
+}
+
+impl CommandOutcome {
+    fn new(command: String, output: Output) -> Self {
+        Self {
+            command,
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            status: output.status,
+        }
+    }
+
+    /// Turns an unsuccessful outcome into an error carrying tmux's stderr and exit code, or into
+    /// [`Error::NoServer`] if tmux could not connect to a server.
+    fn into_result(self) -> Result<Self, Error> {
+        // NOTE: tmux reports a missing socket and a stale one (left behind by a dead server)
+        // differently
+        const NO_SERVER_PATTERNS: [&str; 2] = ["no server running", "error connecting to"];
+
+        if self.status.success() {
+            Ok(self)
+        } else if NO_SERVER_PATTERNS
+            .iter()
+            .any(|pattern| self.stderr.starts_with(pattern))
+        {
+            Err(Error::NoServer)
+        } else {
+            Err(Error::Tmux {
+                command: self.command,
+                stderr: strip_trailing_newline(self.stderr),
+                code: self.status.code(),
+            })
+        }
+    }
+
+    /// Like [`CommandOutcome::into_result`], but keeps both output streams, since tmux reports
+    /// errors in sourced files on stdout.
+    fn into_source_result(self) -> Result<Self, Error> {
+        if self.status.success() {
+            Ok(self)
+        } else {
+            Err(Error::SourceFile {
+                command: self.command,
+                stdout: self.stdout,
+                stderr: self.stderr,
+            })
+        }
+    }
+}
+
+/// Handle for running tmux commands.
+///
+/// Most methods act on the current client or session and therefore require being inside tmux,
+/// which is checked by [`Tmux::try_new`]. Methods that work from outside of tmux, such as
+/// [`Tmux::attach_session`], [`Tmux::switch_or_attach`] and [`Tmux::list_sessions`], can be used
+/// through [`Tmux::new_unchecked`].
+pub struct Tmux {
+    timeout: Option<Duration>,
+    socket: Option<Socket>,
+    /// Executable run for every tmux command
+    binary: PathBuf,
+    /// Version of `binary`, queried once on first use
+    version: OnceLock<(u32, u32)>,
+}
+
+/// Socket of the tmux server commands are sent to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Socket {
+    /// Socket with the given name in tmux's socket directory, passed as `-L <name>`
+    Name(String),
+    /// Socket at the given path, passed as `-S <path>`
+    Path(PathBuf),
+}
 
 impl Tmux {
+    /// Creates a handle, failing with [`Error::NotInTmux`] when not running inside tmux.
     pub fn try_new() -> Result<Self, Error> {
-        match std::env::var("TMUX") {
-            Ok(_) => Ok(Self {}),
-            Err(_) => Err(Error::NotInTmux),
+        if Self::is_inside() {
+            Ok(Self::new_unchecked())
+        } else {
+            Err(Error::NotInTmux)
+        }
+    }
+
+    /// Whether we are running inside tmux, i.e. whether the `TMUX` environment variable is set.
+    pub fn is_inside() -> bool {
+        std::env::var_os("TMUX").is_some()
+    }
+
+    /// Returns the socket path of the tmux server we are running in, as found in the `TMUX`
+    /// environment variable (formatted as `socket,pid,session`).
+    pub fn socket_path() -> Option<PathBuf> {
+        let tmux = std::env::var("TMUX").ok()?;
+
+        parse_socket_path(&tmux)
+    }
+
+    /// Creates a handle without checking whether we are running inside tmux. Useful for commands
+    /// that don't require an attached client, like [`Tmux::attach_session`].
+    ///
+    /// Commands are sent to the server we are running in (see [`Tmux::socket_path`]), or to the
+    /// default server when not running inside tmux.
+    pub fn new_unchecked() -> Self {
+        Self {
+            timeout: None,
+            socket: Self::socket_path().map(Socket::Path),
+            binary: default_binary(),
+            version: OnceLock::new(),
+        }
+    }
+
+    /// Runs the given tmux executable, e.g. `tmux3` or an absolute path, instead of the one from
+    /// the `TMUX_BIN` environment variable or `tmux` found in `PATH`.
+    pub fn with_binary(mut self, binary: PathBuf) -> Self {
+        self.binary = binary;
+        self.version = OnceLock::new();
+        self
+    }
+
+    /// Sends commands to the server with the given socket name (`tmux -L <name>`) instead.
+    pub fn with_socket_name(mut self, name: &str) -> Self {
+        self.socket = Some(Socket::Name(name.to_string()));
+        self
+    }
+
+    /// Sends commands to the server listening on the given socket (`tmux -S <path>`) instead.
+    pub fn with_socket_path(mut self, path: &Path) -> Self {
+        self.socket = Some(Socket::Path(path.to_path_buf()));
+        self
+    }
+
+    /// Kills tmux commands that don't finish within `timeout`, failing them with
+    /// [`Error::Timeout`]. By default commands are waited on indefinitely.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the `(major, minor)` version of tmux, as reported by `tmux -V`. The version is
+    /// only queried once per handle.
+    pub fn version(&self) -> Result<(u32, u32), Error> {
+        if let Some(version) = self.version.get() {
+            return Ok(*version);
         }
+
+        let outcome = self.run_capture(["-V"])?.into_result()?;
+        let version = parse_version(&outcome.stdout).ok_or(Error::VersionParse(outcome.stdout))?;
+
+        Ok(*self.version.get_or_init(|| version))
     }
 
+    /// Fails with [`Error::UnsupportedVersion`] if tmux is older than `major.minor`.
+    pub fn require_version(&self, major: u32, minor: u32) -> Result<(), Error> {
+        let (found_major, found_minor) = self.version()?;
+
+        if (found_major, found_minor) < (major, minor) {
+            return Err(Error::UnsupportedVersion {
+                major: found_major,
+                minor: found_minor,
+                required_major: major,
+                required_minor: minor,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of the given option. The trailing newline tmux always appends is
+    /// removed, any other whitespace in the value is preserved.
     pub fn get_option(&self, option: &str, scope: OptionScope) -> Result<String, Error> {
-        let mut cmd = std::process::Command::new("tmux");
+        self.get_option_inner(option, scope, None)
+    }
 
-        // NOTE: -v makes sure only value is returned without option name
-        cmd.arg("show").arg("-v");
+    /// Like [`Tmux::get_option`], but returns `default` if the option is not set or empty. Other
+    /// errors, e.g. tmux failing to run, are still returned.
+    pub fn get_option_or(
+        &self,
+        option: &str,
+        scope: OptionScope,
+        default: &str,
+    ) -> Result<String, Error> {
+        match self.get_option(option, scope) {
+            Ok(value) if !value.is_empty() => Ok(value),
+            Ok(_) | Err(Error::OptionNotFound(_)) => Ok(default.to_string()),
+            Err(error) => Err(error),
+        }
+    }
 
-        if let Some(scope) = scope.to_arg() {
-            cmd.arg(scope);
+    /// Returns the value of the given boolean option. Accepts the values tmux accepts for flags,
+    /// i.e. `on`/`off`, `yes`/`no`, `true`/`false` and `1`/`0`.
+    pub fn get_option_bool(&self, option: &str, scope: OptionScope) -> Result<bool, Error> {
+        let value = self.get_option(option, scope)?;
+
+        match value.trim().to_ascii_lowercase().as_str() {
+            "on" | "yes" | "true" | "1" => Ok(true),
+            "off" | "no" | "false" | "0" => Ok(false),
+            _ => Err(Error::OptionParse {
+                option: option.to_string(),
+                value,
+            }),
         }
+    }
+
+    /// Returns the value of the given integer option.
+    pub fn get_option_int(&self, option: &str, scope: OptionScope) -> Result<i64, Error> {
+        let value = self.get_option(option, scope)?;
+
+        value.trim().parse().map_err(|_| Error::OptionParse {
+            option: option.to_string(),
+            value,
+        })
+    }
+
+    /// Like [`Tmux::get_option`], but reads the option of the given `target` window or pane
+    /// instead of the current one.
+    pub fn get_target_option(
+        &self,
+        target: &str,
+        option: &str,
+        scope: OptionScope,
+    ) -> Result<String, Error> {
+        self.get_option_inner(option, scope, Some(target))
+    }
+
+    fn get_option_inner(
+        &self,
+        option: &str,
+        scope: OptionScope,
+        target: Option<&str>,
+    ) -> Result<String, Error> {
+        let outcome = self.run_capture(get_option_args(option, scope, target))?;
 
-        let output = cmd.arg(option).output()?;
+        option_value(outcome)
+    }
 
-        if !output.stderr.is_empty() {
-            return Err(Error::OptionNotFound(
-                String::from_utf8(output.stderr).expect("tmux uses utf8"),
-            ));
+    /// Returns all options set in the given scope, keyed by option name.
+    pub fn show_options(&self, scope: OptionScope) -> Result<HashMap<String, String>, Error> {
+        let mut args = vec!["show-options"];
+
+        if let Some(scope) = scope.to_arg() {
+            args.push(scope);
         }
 
-        Ok(read_stdout(output))
+        let outcome = self.run_capture(args)?.into_result()?;
+
+        let options = outcome
+            .stdout
+            .lines()
+            .filter_map(|line| {
+                let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+                (!name.is_empty()).then(|| (name.to_string(), unquote(value).to_string()))
+            })
+            .collect();
+
+        Ok(options)
     }
 
     pub fn set_option(&self, option: &str, value: &str, scope: OptionScope) -> Result<(), Error> {
-        let mut cmd = std::process::Command::new("tmux");
+        self.set_option_inner(option, value, scope, None, false)
+    }
+
+    /// Like [`Tmux::set_option`], but expands formats (e.g. `#{session_name}`) in `value` once,
+    /// when the option is set (`set-option -F`). Values set via [`Tmux::set_option`] are stored
+    /// literally and any formats in them are expanded by tmux each time the option is used.
+    pub fn set_format_option(
+        &self,
+        option: &str,
+        value: &str,
+        scope: OptionScope,
+    ) -> Result<(), Error> {
+        self.set_option_inner(option, value, scope, None, true)
+    }
+
+    /// Like [`Tmux::set_option`], but sets the option on the given `target` window or pane
+    /// instead of the current one.
+    pub fn set_target_option(
+        &self,
+        target: &str,
+        option: &str,
+        value: &str,
+        scope: OptionScope,
+    ) -> Result<(), Error> {
+        self.set_option_inner(option, value, scope, Some(target), false)
+    }
+
+    fn set_option_inner(
+        &self,
+        option: &str,
+        value: &str,
+        scope: OptionScope,
+        target: Option<&str>,
+        expand_formats: bool,
+    ) -> Result<(), Error> {
+        self.run_cmd(set_option_args(
+            option,
+            value,
+            scope,
+            target,
+            expand_formats,
+        ))
+    }
 
-        cmd.arg("set");
+    /// Unsets the given option in the provided scope. Unsetting an option that is not set is a
+    /// no-op.
+    pub fn unset_option(&self, option: &str, scope: OptionScope) -> Result<(), Error> {
+        // NOTE: -q suppresses errors about unknown options, making this a no-op for them
+        let mut args = vec!["set-option", "-u", "-q"];
 
         if let Some(scope) = scope.to_arg() {
-            cmd.arg(scope);
+            args.push(scope);
         }
 
-        cmd.arg(option).arg(value).spawn()?.wait()?;
+        args.push(option);
 
-        Ok(())
+        self.run_cmd(args)
+    }
+
+    /// Sets an environment variable in the current session, or in the global environment when
+    /// `global` is set.
+    pub fn set_env(&self, name: &str, value: &str, global: bool) -> Result<(), Error> {
+        let mut args = vec!["set-environment"];
+
+        if global {
+            args.push("-g");
+        }
+
+        args.extend([name, value]);
+
+        self.run_cmd(args)
+    }
+
+    /// Returns the value of an environment variable in the current session, or in the global
+    /// environment when `global` is set. Variables that are not set, or are marked for removal,
+    /// are returned as `None`.
+    pub fn get_env(&self, name: &str, global: bool) -> Result<Option<String>, Error> {
+        let mut args = vec!["show-environment"];
+
+        if global {
+            args.push("-g");
+        }
+
+        args.push(name);
+
+        let outcome = self.run_capture(args)?;
+
+        if !outcome.status.success() && outcome.stderr.contains("unknown variable") {
+            return Ok(None);
+        }
+
+        let outcome = outcome.into_result()?;
+
+        // NOTE: variables marked for removal are printed as '-NAME'
+        let value = outcome
+            .stdout
+            .lines()
+            .next()
+            .and_then(|line| line.split_once('='))
+            .map(|(_, value)| value.to_string());
+
+        Ok(value)
     }
 
-    fn run_cmd(mut cmd: Command) -> Result<(), Error> {
-        let output = cmd.output()?;
+    /// Sets the hook (e.g. `session-created`) to run the tmux `command`, for the current session or
+    /// globally when `global` is set. Array hooks can be set at a specific index, e.g.
+    /// `session-created[1]`.
+    pub fn set_hook(&self, hook: &str, command: &str, global: bool) -> Result<(), Error> {
+        let mut args = vec!["set-hook"];
 
-        if !output.status.success() {
-            let stdout = String::from_utf8(output.stdout).expect("tmux uses utf8");
-            let stderr = String::from_utf8(output.stderr).expect("tmux uses utf8");
-            return Err(Error::SourceFile { stdout, stderr });
+        if global {
+            args.push("-g");
         }
 
+        args.extend([hook, command]);
+
+        self.run_cmd(args)
+    }
+
+    /// Returns the hooks of the current session, or the global hooks when `global` is set, that
+    /// have a command. Hooks are keyed by their name and index (e.g. `session-created[0]`), the
+    /// commands are kept exactly as printed by tmux so they can be passed to [`Tmux::set_hook`].
+    pub fn show_hooks(&self, global: bool) -> Result<HashMap<String, String>, Error> {
+        let mut args = vec!["show-hooks"];
+
+        if global {
+            args.push("-g");
+        }
+
+        let outcome = self.run_capture(args)?.into_result()?;
+
+        // NOTE: hooks without a command are listed by their name only
+        let hooks = outcome
+            .stdout
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(name, command)| (name.to_string(), command.trim().to_string()))
+            .collect();
+
+        Ok(hooks)
+    }
+
+    /// Runs multiple tmux commands in a single tmux invocation, separated by `;`. Each command is
+    /// given as its name followed by its arguments, e.g. `["set", "-g", "@foo", "bar"]`.
+    pub fn run_commands(&self, commands: &[Vec<String>]) -> Result<(), Error> {
+        if commands.is_empty() {
+            return Ok(());
+        }
+
+        self.run_cmd(batch_args(commands))
+    }
+
+    /// Creates a tmux command targeting the configured server.
+    fn command(&self) -> Command {
+        tmux_command(&self.binary, self.socket.as_ref())
+    }
+
+    /// Runs tmux with the given arguments and captures its output streams and exit status.
+    fn run_capture<I, S>(&self, args: I) -> Result<CommandOutcome, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut cmd = self.command();
+        cmd.args(args);
+
+        let command = format_cmd(&cmd);
+        log::debug!("{command}");
+
+        let output = match self.timeout {
+            Some(timeout) => output_with_timeout(cmd, timeout),
+            None => cmd.output().map(Some),
+        };
+
+        match output {
+            Ok(Some(output)) => Ok(CommandOutcome::new(command, output)),
+            Ok(None) => Err(Error::Timeout(self.timeout.unwrap_or_default())),
+            Err(source) => Err(Error::CommandFailed { command, source }),
+        }
+    }
+
+    /// Runs tmux with the given arguments, failing if tmux exits unsuccessfully.
+    fn run_cmd<I, S>(&self, args: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.run_capture(args)?.into_result()?;
+
         Ok(())
     }
 
     pub fn source_tmux(&self, path: &Path) -> Result<(), Error> {
-        let mut cmd = std::process::Command::new("tmux");
-        cmd.arg("source-file").arg(path);
+        self.run_capture([OsStr::new("source-file"), path.as_os_str()])?
+            .into_source_result()?;
 
-        Self::run_cmd(cmd)
+        Ok(())
     }
 
     pub fn run_shell(&self, path: &Path) -> Result<(), Error> {
-        let mut cmd = std::process::Command::new("tmux");
-        cmd.arg("run-shell").arg("-b").arg(path);
+        self.run_shell_inner(path.as_os_str(), true)
+    }
 
-        Self::run_cmd(cmd)
+    /// Like [`Tmux::run_shell`], but runs the file with the given shell (e.g. `bash`) instead of
+    /// tmux's default shell.
+    pub fn run_shell_with(&self, shell: &str, path: &Path) -> Result<(), Error> {
+        self.run_shell_inner(OsStr::new(&shell_command(shell, path)), true)
     }
 
-    pub fn list_sessions(&self) -> Result<Vec<Session>, Error> {
-        let mut cmd = std::process::Command::new("tmux");
-        cmd.arg("list-sessions");
+    /// Like [`Tmux::run_shell`], but waits for the file to finish instead of running it in the
+    /// background (`run-shell -b`), so files run one after another and fail if the file exits
+    /// unsuccessfully.
+    pub fn run_shell_foreground(&self, path: &Path) -> Result<(), Error> {
+        self.run_shell_inner(path.as_os_str(), false)
+    }
+
+    /// Like [`Tmux::run_shell_with`], but waits for the file to finish, see
+    /// [`Tmux::run_shell_foreground`].
+    pub fn run_shell_foreground_with(&self, shell: &str, path: &Path) -> Result<(), Error> {
+        self.run_shell_inner(OsStr::new(&shell_command(shell, path)), false)
+    }
+
+    fn run_shell_inner(&self, command: &OsStr, background: bool) -> Result<(), Error> {
+        let mut args = vec![OsStr::new("run-shell")];
+
+        if background {
+            args.push(OsStr::new("-b"));
+        }
+
+        args.push(command);
 
-        #[cfg(debug_assertions)]
-        println!("cmd = {}", format_cmd(&cmd));
+        self.run_cmd(args)
+    }
+
+    /// Checks whether the tmux server is running, regardless of whether this process runs inside
+    /// of tmux. Useful to decide between starting a server and attaching to an existing one.
+    pub fn server_running(&self) -> bool {
+        // NOTE: unlike has-session, list-sessions succeeds on a running server without sessions
+        self.run_capture(["list-sessions", "-F", "#{session_id}"])
+            .is_ok_and(|outcome| outcome.status.success())
+    }
 
-        let output = cmd.output()?;
+    /// Lists all sessions of the server. Fails with [`Error::NoServer`] if no server is running,
+    /// a running server without sessions yields an empty list.
+    pub fn list_sessions(&self) -> Result<Vec<Session>, Error> {
+        let outcome = self
+            .run_capture(["list-sessions", "-F", session::LIST_SESSIONS_FORMAT])?
+            .into_result()?;
 
-        let sessions = String::from_utf8(output.stdout)
-            .expect("tmux uses utf8 for names")
+        let sessions = outcome
+            .stdout
             .lines()
-            .map(Session::from)
+            .filter_map(Session::from_formatted)
             .collect();
 
         Ok(sessions)
     }
 
-    pub fn switch_session(&self, session: &Session) -> Result<(), Error> {
-        std::process::Command::new("tmux")
-            .arg("switch-client")
-            .arg("-t")
-            .arg(&session.name)
-            .spawn()?
-            .wait()?;
+    /// Returns all clients currently attached to tmux. Fails with [`Error::NoServer`] when no
+    /// tmux server is running.
+    pub fn list_clients(&self) -> Result<Vec<Client>, Error> {
+        let outcome = self
+            .run_capture(["list-clients", "-F", client::LIST_CLIENTS_FORMAT])?
+            .into_result()?;
 
-        Ok(())
+        let clients = outcome
+            .stdout
+            .lines()
+            .filter_map(Client::from_formatted)
+            .collect();
+
+        Ok(clients)
     }
 
-    pub fn display_message(&self, message: &str) -> Result<(), Error> {
-        std::process::Command::new("tmux")
-            .arg("display-message")
-            .arg(message)
-            .spawn()?
-            .wait()?;
+    /// Whether at least one client is attached to tmux, i.e. whether there is anywhere to display
+    /// messages. Reports `false` when no tmux server is running.
+    pub fn has_attached_client(&self) -> Result<bool, Error> {
+        match self.list_clients() {
+            Ok(clients) => Ok(!clients.is_empty()),
+            Err(Error::NoServer) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Whether a session with exactly the given name exists. Works from outside of tmux, and
+    /// reports `false` when no tmux server is running.
+    pub fn has_session(&self, name: &str) -> Result<bool, Error> {
+        // NOTE: '=' prevents tmux from matching session names by prefix
+        let outcome = self.run_capture(["has-session", "-t", &format!("={name}")])?;
+
+        Ok(outcome.status.success())
+    }
+
+    /// Creates a new detached session with the given name, starting in the working directory
+    /// `cwd` if provided. Works from outside of tmux, starting the tmux server if needed. Fails
+    /// with [`Error::SessionExists`] if a session with that name already exists.
+    pub fn new_session(&self, name: &str, cwd: Option<&Path>) -> Result<Session, Error> {
+        let mut args = vec![
+            OsStr::new("new-session"),
+            OsStr::new("-d"),
+            OsStr::new("-P"),
+            OsStr::new("-F"),
+            OsStr::new(session::LIST_SESSIONS_FORMAT),
+            OsStr::new("-s"),
+            OsStr::new(name),
+        ];
+
+        if let Some(cwd) = cwd {
+            args.extend([OsStr::new("-c"), cwd.as_os_str()]);
+        }
+
+        let outcome = self
+            .run_capture(args)?
+            .into_result()
+            .map_err(|error| match error {
+                Error::Tmux { stderr, .. } if stderr.contains("duplicate session") => {
+                    Error::SessionExists(name.to_string())
+                }
+                error => error,
+            })?;
+
+        outcome
+            .stdout
+            .lines()
+            .next()
+            .and_then(Session::from_formatted)
+            .ok_or(Error::UnexpectedOutput {
+                command: outcome.command,
+                output: outcome.stdout,
+            })
+    }
+
+    /// Attaches to the session with the given name. Unlike [`Tmux::switch_session`], this works
+    /// from outside of tmux and takes over the current terminal until the client detaches.
+    pub fn attach_session(&self, name: &str) -> Result<(), Error> {
+        // NOTE: attaching needs the terminal, so the streams are inherited instead of captured
+        let mut cmd = self.command();
+        cmd.args(["attach-session", "-t", name]);
+
+        let command = format_cmd(&cmd);
+        log::debug!("{command}");
+        let status = cmd.status().map_err(|source| Error::CommandFailed {
+            command: command.clone(),
+            source,
+        })?;
+
+        if !status.success() {
+            return Err(Error::Tmux {
+                command,
+                stderr: format!("could not attach to session '{name}'"),
+                code: status.code(),
+            });
+        }
 
         Ok(())
     }
 
+    /// Switches the current client to the given session. Requires being inside tmux.
+    pub fn switch_session(&self, session: &Session) -> Result<(), Error> {
+        self.run_cmd(["switch-client", "-t", &session.name])
+    }
+
+    /// Redraws the given client, or the current client when no target is provided. With
+    /// `status_only`, only the status line is redrawn (`refresh-client -S`).
+    pub fn refresh_client(&self, target: Option<&str>, status_only: bool) -> Result<(), Error> {
+        let mut args = vec!["refresh-client"];
+
+        if status_only {
+            args.push("-S");
+        }
+
+        if let Some(target) = target {
+            args.extend(["-t", target]);
+        }
+
+        self.run_cmd(args)
+    }
+
+    /// Goes to the given session, wherever this process runs. Inside tmux, the current client is
+    /// switched to it like with [`Tmux::switch_session`] and this returns immediately. From a plain
+    /// shell, the session is attached like with [`Tmux::attach_session`], taking over the terminal
+    /// until the client detaches.
+    pub fn switch_or_attach(&self, session: &Session) -> Result<(), Error> {
+        if Self::is_inside() {
+            self.switch_session(session)
+        } else {
+            self.attach_session(&session.name)
+        }
+    }
+
+    /// Renames the given session, returning the session with its updated name.
+    pub fn rename_session(&self, session: &Session, new_name: &str) -> Result<Session, Error> {
+        self.run_cmd(["rename-session", "-t", &session.name, new_name])
+            .map_err(|error| match error {
+                Error::Tmux { stderr, .. } if stderr.contains("duplicate session") => {
+                    Error::SessionExists(new_name.to_string())
+                }
+                error => error,
+            })?;
+
+        Ok(Session {
+            name: new_name.to_string(),
+            windows: session.windows,
+            state: session.state,
+            attached_clients: session.attached_clients,
+            created: session.created,
+            group: session.group.clone(),
+        })
+    }
+
+    /// Renames the window identified by `target` (e.g. `session:index`).
+    pub fn rename_window(&self, target: &str, new_name: &str) -> Result<(), Error> {
+        self.run_cmd(["rename-window", "-t", target, new_name])
+    }
+
+    /// Creates a new window in the given session, optionally named `name` and starting in the
+    /// working directory `cwd`.
+    pub fn new_window(
+        &self,
+        session: &Session,
+        name: Option<&str>,
+        cwd: Option<&Path>,
+    ) -> Result<Window, Error> {
+        // NOTE: the trailing ':' targets the session, creating the window at the next free index
+        let target = format!("{}:", session.name);
+        let mut args = vec![
+            OsStr::new("new-window"),
+            OsStr::new("-P"),
+            OsStr::new("-F"),
+            OsStr::new(window::WINDOW_FORMAT),
+            OsStr::new("-t"),
+            OsStr::new(&target),
+        ];
+
+        if let Some(name) = name {
+            args.extend([OsStr::new("-n"), OsStr::new(name)]);
+        }
+
+        if let Some(cwd) = cwd {
+            args.extend([OsStr::new("-c"), cwd.as_os_str()]);
+        }
+
+        let outcome = self.run_capture(args)?.into_result()?;
+
+        outcome
+            .stdout
+            .lines()
+            .next()
+            .and_then(Window::from_formatted)
+            .ok_or(Error::UnexpectedOutput {
+                command: outcome.command,
+                output: outcome.stdout,
+            })
+    }
+
+    /// Splits the pane identified by `target`. When `vertical` is set, the new pane is placed
+    /// below the target (`-v`), otherwise next to it (`-h`). The new pane starts in the working
+    /// directory `cwd`, if provided.
+    pub fn split_window(
+        &self,
+        target: &str,
+        vertical: bool,
+        cwd: Option<&Path>,
+    ) -> Result<(), Error> {
+        let orientation = if vertical { "-v" } else { "-h" };
+        let mut args = vec![
+            OsStr::new("split-window"),
+            OsStr::new(orientation),
+            OsStr::new("-t"),
+            OsStr::new(target),
+        ];
+
+        if let Some(cwd) = cwd {
+            args.extend([OsStr::new("-c"), cwd.as_os_str()]);
+        }
+
+        self.run_cmd(args)
+    }
+
+    /// Returns the panes of the window identified by `target`, along with their size, e.g. to
+    /// snapshot a layout.
+    pub fn list_panes(&self, target: &str) -> Result<Vec<Pane>, Error> {
+        let outcome = self
+            .run_capture(["list-panes", "-t", target, "-F", pane::LIST_PANES_FORMAT])?
+            .into_result()?;
+
+        let panes = outcome
+            .stdout
+            .lines()
+            .filter_map(Pane::from_formatted)
+            .collect();
+
+        Ok(panes)
+    }
+
+    /// Kills the window identified by `target`, along with all of its panes. Killing the last
+    /// window of a session also kills the session. Fails with [`Error::TargetNotFound`] if the
+    /// window doesn't exist (anymore).
+    pub fn kill_window(&self, target: &str) -> Result<(), Error> {
+        self.kill_target("kill-window", target)
+    }
+
+    /// Kills the pane identified by `target`. Killing the last pane of a window also kills the
+    /// window, and with it possibly the session. Fails with [`Error::TargetNotFound`] if the pane
+    /// doesn't exist (anymore).
+    pub fn kill_pane(&self, target: &str) -> Result<(), Error> {
+        self.kill_target("kill-pane", target)
+    }
+
+    fn kill_target(&self, command: &str, target: &str) -> Result<(), Error> {
+        match self.run_cmd([command, "-t", target]) {
+            Err(Error::Tmux { stderr, .. }) if stderr.contains("can't find") => {
+                Err(Error::TargetNotFound(target.to_string()))
+            }
+            result => result,
+        }
+    }
+
+    /// Applies the layout to the window identified by `target`.
+    pub fn select_layout(&self, target: &str, layout: Layout) -> Result<(), Error> {
+        self.run_cmd(["select-layout", "-t", target, layout.as_str()])
+    }
+
+    /// Selects the window identified by `target`. The target is passed to tmux verbatim, so
+    /// indices, names and tokens such as `{last}` or `{next}` are all supported.
+    pub fn select_window(&self, target: &str) -> Result<(), Error> {
+        self.run_cmd(["select-window", "-t", target])
+    }
+
+    /// Selects the pane identified by `target`. The target is passed to tmux verbatim, so
+    /// indices and tokens such as `{last}` or `{next}` are all supported.
+    pub fn select_pane(&self, target: &str) -> Result<(), Error> {
+        self.run_cmd(["select-pane", "-t", target])
+    }
+
+    /// Sends raw bytes (e.g. escape sequences or mouse reports) to the pane identified by
+    /// `target`, using `send-keys -H`.
+    pub fn send_hex(&self, target: &str, bytes: &[u8]) -> Result<(), Error> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+
+        let mut args = vec![
+            "send-keys".to_string(),
+            "-H".to_string(),
+            "-t".to_string(),
+            target.to_string(),
+        ];
+        args.extend(hex_keys(bytes));
+
+        self.run_cmd(args)
+    }
+
+    /// Stores `data` in the paste buffer with the given name, or in a new automatically named
+    /// buffer when no name is provided.
+    pub fn set_buffer(&self, data: &str, name: Option<&str>) -> Result<(), Error> {
+        let mut args = vec!["set-buffer"];
+
+        if let Some(name) = name {
+            args.extend(["-b", name]);
+        }
+
+        // NOTE: -- keeps data starting with a dash from being parsed as a flag
+        args.extend(["--", data]);
+
+        self.run_cmd(args)
+    }
+
+    /// Returns the contents of the paste buffer with the given name, or of the most recent buffer
+    /// when no name is provided. Buffers may contain arbitrary bytes, invalid UTF-8 is replaced.
+    pub fn get_buffer(&self, name: Option<&str>) -> Result<String, Error> {
+        let mut args = vec!["show-buffer"];
+
+        if let Some(name) = name {
+            args.extend(["-b", name]);
+        }
+
+        let outcome = self.run_capture(args)?.into_result()?;
+
+        Ok(outcome.stdout)
+    }
+
+    /// Pastes the paste buffer with the given name, or the most recent buffer when no name is
+    /// provided, into the pane identified by `target`.
+    pub fn paste_buffer(&self, target: &str, name: Option<&str>) -> Result<(), Error> {
+        let mut args = vec!["paste-buffer", "-t", target];
+
+        if let Some(name) = name {
+            args.extend(["-b", name]);
+        }
+
+        self.run_cmd(args)
+    }
+
+    /// Binds `key` to the tmux `command` (given as its name followed by its arguments, e.g.
+    /// `["split-window", "-h"]`) in the given key table, or in the prefix table when no table is
+    /// provided. The `root` table, i.e. keys working without the prefix, is bound using `-n`.
+    pub fn bind_key(&self, key: &str, command: &[&str], table: Option<&str>) -> Result<(), Error> {
+        let mut args = vec!["bind-key"];
+        args.extend(key_table_args(table));
+        args.push(key);
+        args.extend(command);
+
+        self.run_cmd(args)
+    }
+
+    /// Removes the binding of `key` from the given key table, or from the prefix table when no
+    /// table is provided.
+    pub fn unbind_key(&self, key: &str, table: Option<&str>) -> Result<(), Error> {
+        let mut args = vec!["unbind-key"];
+        args.extend(key_table_args(table));
+        args.push(key);
+
+        self.run_cmd(args)
+    }
+
+    /// Returns the key bindings of the given key table, or of all tables when no table is
+    /// provided.
+    pub fn list_keys(&self, table: Option<&str>) -> Result<Vec<KeyBinding>, Error> {
+        let mut args = vec!["list-keys"];
+
+        if let Some(table) = table {
+            args.extend(["-T", table]);
+        }
+
+        let outcome = self.run_capture(args)?.into_result()?;
+
+        let bindings = outcome
+            .stdout
+            .lines()
+            .filter_map(KeyBinding::from_list_keys)
+            .collect();
+
+        Ok(bindings)
+    }
+
+    /// Displays a popup running the given shell command. When `close_on_exit` is set, the popup
+    /// is closed as soon as the command exits. Requires tmux 3.2 or newer.
+    pub fn display_popup(
+        &self,
+        command: &str,
+        width: Option<&str>,
+        height: Option<&str>,
+        close_on_exit: bool,
+    ) -> Result<(), Error> {
+        self.require_version(3, 2)?;
+
+        let mut args = vec!["display-popup"];
+
+        if close_on_exit {
+            args.push("-E");
+        }
+
+        if let Some(width) = width {
+            args.extend(["-w", width]);
+        }
+
+        if let Some(height) = height {
+            args.extend(["-h", height]);
+        }
+
+        args.push(command);
+
+        self.run_cmd(args)
+    }
+
+    /// Expands the tmux format string (e.g. `#{session_name}`) for the given `target`, or for the
+    /// current pane when no target is provided.
+    pub fn format(&self, target: Option<&str>, fmt: &str) -> Result<String, Error> {
+        let mut args = vec!["display-message", "-p"];
+
+        if let Some(target) = target {
+            args.extend(["-t", target]);
+        }
+
+        args.push(fmt);
+
+        let outcome = self.run_capture(args)?.into_result()?;
+
+        Ok(strip_trailing_newline(outcome.stdout))
+    }
+
+    pub fn display_message(&self, message: &str) -> Result<(), Error> {
+        self.run_cmd(["display-message", message])
+    }
+
     pub fn display_message_with_duration(
         &self,
         message: &str,
         duration_ms: u32,
     ) -> Result<(), Error> {
-        std::process::Command::new("tmux")
-            .arg("display-message")
-            .arg("-d")
-            .arg(duration_ms.to_string())
-            .arg(message)
-            .spawn()?
-            .wait()?;
+        // NOTE: display-message -d was introduced in tmux 3.2
+        self.require_version(3, 2)?;
 
-        Ok(())
+        self.run_cmd(["display-message", "-d", &duration_ms.to_string(), message])
     }
 }
 
@@ -186,7 +1115,657 @@ impl OptionScope {
     }
 }
 
-fn read_stdout(output: Output) -> String {
-    let stdout = output.stdout;
-    String::from_utf8(stdout).expect("tmux uses utf8")
+/// Arguments selecting the key table of `bind-key` and `unbind-key`.
+fn key_table_args(table: Option<&str>) -> Vec<&str> {
+    match table {
+        Some("root") => vec!["-n"],
+        Some(table) => vec!["-T", table],
+        None => Vec::new(),
+    }
+}
+
+/// Returns the tmux executable to run by default, taken from the `TMUX_BIN` environment variable
+/// if set.
+fn default_binary() -> PathBuf {
+    std::env::var_os("TMUX_BIN")
+        .filter(|binary| !binary.is_empty())
+        .map_or_else(|| PathBuf::from("tmux"), PathBuf::from)
+}
+
+/// Creates a command running the tmux `binary`, sent to the server listening on `socket` or to the
+/// default server. All tmux commands are built here, so global flags only need to be added in one
+/// place.
+fn tmux_command(binary: &Path, socket: Option<&Socket>) -> Command {
+    let mut cmd = Command::new(binary);
+
+    match socket {
+        Some(Socket::Name(name)) => cmd.arg("-L").arg(name),
+        Some(Socket::Path(path)) => cmd.arg("-S").arg(path),
+        None => &mut cmd,
+    };
+
+    cmd
+}
+
+/// Runs the command like [`Command::output`], but kills it if it doesn't finish within `timeout`.
+/// Returns `None` if the command timed out.
+fn output_with_timeout(mut cmd: Command, timeout: Duration) -> io::Result<Option<Output>> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // NOTE: streams are drained concurrently so that tmux never blocks on a full pipe
+    let stdout = child.stdout.take().map(read_to_end_in_background);
+    let stderr = child.stderr.take().map(read_to_end_in_background);
+
+    let deadline = Instant::now() + timeout;
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+
+        std::thread::sleep(Duration::from_millis(5));
+    };
+
+    let join = |reader: Option<std::thread::JoinHandle<Vec<u8>>>| {
+        reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default()
+    };
+
+    Ok(Some(Output {
+        status,
+        stdout: join(stdout),
+        stderr: join(stderr),
+    }))
+}
+
+/// Reads the stream to its end on a separate thread.
+fn read_to_end_in_background(
+    mut stream: impl Read + Send + 'static,
+) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stream.read_to_end(&mut buf);
+        buf
+    })
+}
+
+/// Formats every byte as a two digit hex key, as expected by `send-keys -H`.
+fn hex_keys(bytes: &[u8]) -> impl Iterator<Item = String> {
+    bytes.iter().map(|byte| format!("{byte:02x}"))
+}
+
+/// Builds the shell command running the file at `path` with `shell`, quoting the path.
+fn shell_command(shell: &str, path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\'', r"'\''");
+    format!("{shell} '{path}'")
+}
+
+/// Builds the arguments of `tmux show` for reading a single option value.
+fn get_option_args<'a>(
+    option: &'a str,
+    scope: OptionScope,
+    target: Option<&'a str>,
+) -> Vec<&'a str> {
+    // NOTE: -v makes sure only value is returned without option name
+    let mut args = vec!["show", "-v"];
+
+    if let Some(scope) = scope.to_arg() {
+        args.push(scope);
+    }
+
+    if let Some(target) = target {
+        args.extend(["-t", target]);
+    }
+
+    args.push(option);
+
+    args
+}
+
+/// Extracts the option value from the outcome of `tmux show -v`. Options that are set, but
+/// empty, have an empty value. Only unknown or unset options are reported as
+/// [`Error::OptionNotFound`], other failures as [`Error::Tmux`] or [`Error::NoServer`].
+fn option_value(outcome: CommandOutcome) -> Result<String, Error> {
+    // NOTE: tmux reports unset user options like unknown ones, older versions as "unknown option"
+    const NOT_FOUND_PATTERNS: [&str; 2] = ["invalid option", "unknown option"];
+
+    if !outcome.status.success()
+        && NOT_FOUND_PATTERNS
+            .iter()
+            .any(|pattern| outcome.stderr.starts_with(pattern))
+    {
+        return Err(Error::OptionNotFound(strip_trailing_newline(
+            outcome.stderr,
+        )));
+    }
+
+    let outcome = outcome.into_result()?;
+
+    Ok(strip_trailing_newline(outcome.stdout))
+}
+
+/// Describes how tmux exited, for error messages.
+fn describe_exit_code(code: Option<i32>) -> String {
+    match code {
+        Some(code) => format!("exit code {code}"),
+        None => "terminated by a signal".to_string(),
+    }
+}
+
+/// Builds the arguments of `tmux set` for setting a single option value.
+fn set_option_args<'a>(
+    option: &'a str,
+    value: &'a str,
+    scope: OptionScope,
+    target: Option<&'a str>,
+    expand_formats: bool,
+) -> Vec<&'a str> {
+    let mut args = vec!["set"];
+
+    if let Some(scope) = scope.to_arg() {
+        args.push(scope);
+    }
+
+    if expand_formats {
+        args.push("-F");
+    }
+
+    if let Some(target) = target {
+        args.extend(["-t", target]);
+    }
+
+    // NOTE: values starting with '-' would otherwise be parsed as flags by tmux
+    if value.starts_with('-') {
+        args.push("--");
+    }
+
+    args.extend([option, value]);
+
+    args
+}
+
+/// Builds the argument list for running all commands in one tmux invocation.
+fn batch_args(commands: &[Vec<String>]) -> Vec<String> {
+    let mut args = Vec::new();
+
+    for (i, command) in commands.iter().enumerate() {
+        if i > 0 {
+            args.push(";".to_string());
+        }
+
+        args.extend(command.iter().map(|arg| escape_command_arg(arg)));
+    }
+
+    args
+}
+
+/// Escapes an argument so that tmux does not treat a trailing `;` as a command separator.
+fn escape_command_arg(arg: &str) -> String {
+    match arg.strip_suffix(';') {
+        Some(rest) => format!("{rest}\\;"),
+        None => arg.to_string(),
+    }
+}
+
+/// Removes exactly one trailing newline (`\n` or `\r\n`) from the value, preserving any other
+/// whitespace.
+fn strip_trailing_newline(mut value: String) -> String {
+    if value.ends_with('\n') {
+        value.pop();
+
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+
+    value
+}
+
+/// Extracts the socket path from the value of the `TMUX` environment variable. The path itself
+/// may contain commas, so the pid and session are split off from the end.
+fn parse_socket_path(tmux: &str) -> Option<PathBuf> {
+    let mut parts = tmux.rsplitn(3, ',');
+    let socket = parts.nth(2)?;
+
+    (!socket.is_empty()).then(|| PathBuf::from(socket))
+}
+
+/// Parses the output of `tmux -V` into `(major, minor)`. Handles suffixed versions such as
+/// `tmux 3.3a` or `tmux next-3.4`.
+fn parse_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.trim().rsplit(' ').next()?;
+    let version = version.rsplit('-').next()?;
+
+    let (major, rest) = version.split_once('.')?;
+    let minor: String = rest.chars().take_while(char::is_ascii_digit).collect();
+
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Removes the quotes tmux wraps around option values containing special characters.
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| {
+            value
+                .strip_prefix(quote)
+                .and_then(|value| value.strip_suffix(quote))
+        })
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use super::*;
+
+    /// Fake tmux executable. Its behavior is selected by the name it is run as, i.e. by the
+    /// scenario passed to [`stub_tmux`].
+    const STUB_TMUX: &str = r#"#!/bin/sh
+scenario=$(basename "$0")
+
+case "$scenario" in
+capture)
+    echo "to stdout"
+    echo "to stderr" >&2
+    exit 3
+    ;;
+version)
+    echo "tmux 3.3a"
+    ;;
+args)
+    printf '%s\n' "$@" >&2
+    exit 1
+    ;;
+unexpected)
+    echo "unexpected output"
+    ;;
+no-server)
+    echo "no server running on /tmp/tmux-1000/no-server" >&2
+    exit 1
+    ;;
+stale-socket)
+    echo "error connecting to /tmp/tmux-1000/stale-socket (Connection refused)" >&2
+    exit 1
+    ;;
+no-clients | no-sessions) ;;
+one-client)
+    printf '/dev/pts/3\037main\037120\03740\n'
+    ;;
+esac
+"#;
+
+    /// Returns a handle running the fake tmux executable in the given scenario. No socket is
+    /// passed, even inside tmux, so the stub only sees the arguments of the command.
+    fn stub_tmux(scenario: &str) -> Tmux {
+        Tmux {
+            socket: None,
+            ..Tmux::new_unchecked().with_binary(stub_binary(scenario))
+        }
+    }
+
+    /// Returns the path of the fake tmux executable running the given scenario, a symlink named
+    /// after the scenario.
+    fn stub_binary(scenario: &str) -> PathBuf {
+        // NOTE: the stub is written once, before any test runs it, so no test ever executes a
+        // file that is still open for writing
+        static STUB: OnceLock<PathBuf> = OnceLock::new();
+
+        let stub = STUB.get_or_init(|| {
+            let dir = std::env::temp_dir().join(format!("murus-stub-tmux-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+
+            let stub = dir.join("tmux");
+            fs::write(&stub, STUB_TMUX).unwrap();
+            fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+            stub
+        });
+
+        let binary = stub.with_file_name(scenario);
+        match std::os::unix::fs::symlink(stub, &binary) {
+            Err(error) if error.kind() != io::ErrorKind::AlreadyExists => panic!("{error}"),
+            _ => binary,
+        }
+    }
+
+    /// Returns the arguments tmux was run with in the `args` scenario, which prints them to
+    /// stderr and fails.
+    fn stub_args<T: std::fmt::Debug>(result: Result<T, Error>) -> Vec<String> {
+        match result {
+            Err(Error::Tmux { stderr, .. }) => stderr.lines().map(str::to_string).collect(),
+            other => panic!("expected the stub to fail, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn run_capture_captures_streams_and_status() {
+        let outcome = stub_tmux("capture").run_capture(["anything"]).unwrap();
+
+        assert_eq!(outcome.stdout, "to stdout\n");
+        assert_eq!(outcome.stderr, "to stderr\n");
+        assert_eq!(outcome.status.code(), Some(3));
+    }
+
+    #[test]
+    fn parse_version_with_suffixes() {
+        assert_eq!(parse_version("tmux 3.4\n"), Some((3, 4)));
+        assert_eq!(parse_version("tmux 3.3a\n"), Some((3, 3)));
+        assert_eq!(parse_version("tmux next-3.4\n"), Some((3, 4)));
+        assert_eq!(parse_version("tmux master\n"), None);
+    }
+
+    #[test]
+    fn require_version_compares_with_installed_version() {
+        let tmux = stub_tmux("version");
+
+        assert_eq!(tmux.version().unwrap(), (3, 3));
+        assert!(tmux.require_version(3, 2).is_ok());
+        assert!(matches!(
+            tmux.require_version(3, 4),
+            Err(Error::UnsupportedVersion {
+                major: 3,
+                minor: 3,
+                required_major: 3,
+                required_minor: 4,
+            })
+        ));
+    }
+
+    #[test]
+    fn strip_trailing_newline_keeps_other_whitespace() {
+        assert_eq!(strip_trailing_newline("on\n".to_string()), "on");
+        assert_eq!(strip_trailing_newline("  x  \n".to_string()), "  x  ");
+        assert_eq!(strip_trailing_newline("crlf\r\n".to_string()), "crlf");
+        assert_eq!(strip_trailing_newline("two\n\n".to_string()), "two\n");
+        assert_eq!(strip_trailing_newline(String::new()), "");
+    }
+
+    #[test]
+    fn list_clients_parses_attached_clients() {
+        assert!(stub_tmux("no-clients").list_clients().unwrap().is_empty());
+        assert_eq!(
+            stub_tmux("one-client").list_clients().unwrap(),
+            [Client {
+                tty: "/dev/pts/3".to_string(),
+                session: "main".to_string(),
+                size: (120, 40),
+            }]
+        );
+    }
+
+    #[test]
+    fn list_clients_without_server() {
+        let tmux = stub_tmux("no-server");
+
+        assert!(matches!(tmux.list_clients(), Err(Error::NoServer)));
+        assert!(!tmux.has_attached_client().unwrap());
+    }
+
+    #[test]
+    fn set_option_args_separate_dash_leading_values() {
+        assert_eq!(
+            set_option_args("@opt", "-1", OptionScope::Global, None, false),
+            ["set", "-g", "--", "@opt", "-1"]
+        );
+        assert_eq!(
+            set_option_args("@opt", "on", OptionScope::Global, None, false),
+            ["set", "-g", "@opt", "on"]
+        );
+        assert_eq!(
+            set_option_args("@opt", "--x", OptionScope::Pane, Some("%1"), true),
+            ["set", "-p", "-F", "-t", "%1", "--", "@opt", "--x"]
+        );
+    }
+
+    #[test]
+    fn transient_errors_are_retried() {
+        let lost_server = Error::Tmux {
+            command: "tmux source-file plugin.tmux".to_string(),
+            stderr: "lost server".to_string(),
+            code: Some(1),
+        };
+        let busy_while_sourcing = Error::SourceFile {
+            command: "tmux source-file plugin.tmux".to_string(),
+            stdout: "Resource temporarily unavailable\n".to_string(),
+            stderr: String::new(),
+        };
+
+        assert!(lost_server.is_transient());
+        assert!(busy_while_sourcing.is_transient());
+    }
+
+    #[test]
+    fn config_errors_are_not_retried() {
+        let unknown_command = Error::SourceFile {
+            command: "tmux source-file plugin.tmux".to_string(),
+            stdout: "plugin.tmux:1: unknown command: set-clock\n".to_string(),
+            stderr: String::new(),
+        };
+        let invalid_option = Error::Tmux {
+            command: "tmux set -g @lock-after on".to_string(),
+            stderr: "invalid option: lock-after".to_string(),
+            code: Some(1),
+        };
+
+        assert!(!unknown_command.is_transient());
+        assert!(!invalid_option.is_transient());
+        assert!(!Error::NoServer.is_transient());
+    }
+
+    #[test]
+    fn batch_args_separate_commands() {
+        let commands = [
+            vec![
+                "set".to_string(),
+                "-g".to_string(),
+                "@a".to_string(),
+                "1".to_string(),
+            ],
+            vec![
+                "bind".to_string(),
+                "x".to_string(),
+                "display hi;".to_string(),
+            ],
+            vec!["refresh-client".to_string()],
+        ];
+
+        assert_eq!(
+            batch_args(&commands),
+            [
+                "set",
+                "-g",
+                "@a",
+                "1",
+                ";",
+                "bind",
+                "x",
+                "display hi\\;",
+                ";",
+                "refresh-client",
+            ]
+        );
+    }
+
+    #[test]
+    fn escape_command_arg_escapes_trailing_separator() {
+        assert_eq!(escape_command_arg("a;"), "a\\;");
+        assert_eq!(escape_command_arg("a;b"), "a;b");
+        assert_eq!(escape_command_arg("plain"), "plain");
+    }
+
+    #[test]
+    fn has_attached_client_from_client_count() {
+        assert!(!stub_tmux("no-clients").has_attached_client().unwrap());
+        assert!(stub_tmux("one-client").has_attached_client().unwrap());
+    }
+
+    #[test]
+    fn shell_command_quotes_path() {
+        assert_eq!(
+            shell_command("bash", Path::new("/plugins/yank/yank.tmux")),
+            "bash '/plugins/yank/yank.tmux'"
+        );
+        assert_eq!(
+            shell_command("bash", Path::new("/plugins/it's/a b.tmux")),
+            r"bash '/plugins/it'\''s/a b.tmux'"
+        );
+    }
+
+    #[test]
+    fn run_shell_with_and_without_shell() {
+        let tmux = stub_tmux("args");
+        let path = Path::new("/plugins/yank/yank.tmux");
+
+        assert_eq!(
+            stub_args(tmux.run_shell(path)),
+            ["run-shell", "-b", "/plugins/yank/yank.tmux"]
+        );
+        assert_eq!(
+            stub_args(tmux.run_shell_with("bash", path)),
+            ["run-shell", "-b", "bash '/plugins/yank/yank.tmux'"]
+        );
+    }
+
+    #[test]
+    fn hex_keys_are_two_digit_pairs() {
+        assert_eq!(
+            hex_keys(&[0x1b, b'[', b'A', 0x00, 0xff]).collect::<Vec<_>>(),
+            ["1b", "5b", "41", "00", "ff"]
+        );
+    }
+
+    #[test]
+    fn send_hex_passes_keys_as_separate_arguments() {
+        let tmux = stub_tmux("args");
+
+        assert_eq!(
+            stub_args(tmux.send_hex("%1", b"\x1b[A")),
+            ["send-keys", "-H", "-t", "%1", "1b", "5b", "41"]
+        );
+        assert!(tmux.send_hex("%1", &[]).is_ok());
+    }
+
+    #[test]
+    fn unparsable_new_session_and_window_output() {
+        let tmux = stub_tmux("unexpected");
+        let session = Session {
+            name: "main".to_string(),
+            windows: 1,
+            state: session::State::Detached,
+            attached_clients: 0,
+            created: None,
+            group: None,
+        };
+
+        assert!(matches!(
+            tmux.new_session("main", None),
+            Err(Error::UnexpectedOutput { output, .. }) if output == "unexpected output\n"
+        ));
+        assert!(matches!(
+            tmux.new_window(&session, Some("logs"), None),
+            Err(Error::UnexpectedOutput { output, .. }) if output == "unexpected output\n"
+        ));
+    }
+
+    #[test]
+    fn failed_command_error_contains_argv() {
+        let error = stub_tmux("capture")
+            .run_cmd(["display-message", "-p", "hello world"])
+            .unwrap_err();
+
+        let message = error.to_string();
+        assert!(message.contains("capture display-message -p"), "{message}");
+        assert!(message.contains("hello world"), "{message}");
+        assert!(message.contains("exit code 3"), "{message}");
+    }
+
+    #[test]
+    fn spawn_failure_error_contains_argv() {
+        let error = Tmux::new_unchecked()
+            .with_binary(PathBuf::from("/nonexistent/tmux"))
+            .with_socket_name("test")
+            .run_cmd(["list-sessions"])
+            .unwrap_err();
+
+        assert!(matches!(error, Error::CommandFailed { .. }));
+        assert!(
+            error
+                .to_string()
+                .contains("/nonexistent/tmux -L test list-sessions"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn parse_socket_path_from_tmux_variable() {
+        assert_eq!(
+            parse_socket_path("/tmp/tmux-1000/default,1234,0"),
+            Some(PathBuf::from("/tmp/tmux-1000/default"))
+        );
+        assert_eq!(
+            parse_socket_path("/tmp/odd,name/default,1234,0"),
+            Some(PathBuf::from("/tmp/odd,name/default"))
+        );
+        assert_eq!(parse_socket_path(",1234,0"), None);
+        assert_eq!(parse_socket_path("/tmp/tmux-1000/default"), None);
+    }
+
+    #[test]
+    fn command_runs_tmux() {
+        let cmd = tmux_command(Path::new("tmux"), None);
+
+        assert_eq!(cmd.get_program(), "tmux");
+        assert_eq!(cmd.get_args().count(), 0);
+    }
+
+    #[test]
+    fn command_targets_socket() {
+        let by_name = tmux_command(Path::new("tmux"), Some(&Socket::Name("work".to_string())));
+        let by_path = tmux_command(
+            Path::new("tmux"),
+            Some(&Socket::Path(PathBuf::from("/tmp/tmux-1000/work"))),
+        );
+
+        assert_eq!(by_name.get_program(), "tmux");
+        assert_eq!(by_name.get_args().collect::<Vec<_>>(), ["-L", "work"]);
+        assert_eq!(by_path.get_program(), "tmux");
+        assert_eq!(
+            by_path.get_args().collect::<Vec<_>>(),
+            ["-S", "/tmp/tmux-1000/work"]
+        );
+    }
+
+    #[test]
+    fn list_sessions_without_server() {
+        let tmux = stub_tmux("no-server");
+
+        assert!(matches!(tmux.list_sessions(), Err(Error::NoServer)));
+        assert!(!tmux.server_running());
+
+        // A socket left behind by a server that died is reported differently
+        let tmux = stub_tmux("stale-socket");
+
+        assert!(matches!(tmux.list_sessions(), Err(Error::NoServer)));
+        assert!(!tmux.server_running());
+    }
+
+    #[test]
+    fn list_sessions_on_server_without_sessions() {
+        let tmux = stub_tmux("no-sessions");
+
+        assert_eq!(tmux.list_sessions().unwrap(), []);
+        assert!(tmux.server_running());
+    }
 }
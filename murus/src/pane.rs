@@ -0,0 +1,71 @@
+/// Format passed to `tmux list-panes -F`, fields separated by the ASCII unit separator. Parsed by
+/// [`Pane::from_formatted`].
+pub(crate) const LIST_PANES_FORMAT: &str =
+    "#{pane_id}\x1f#{pane_index}\x1f#{pane_width}\x1f#{pane_height}\x1f#{pane_active}";
+
+/// Represents a single pane of a window in tmux, including its geometry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pane {
+    /// Unique id of the pane, e.g. `%5`. Can be used as a target.
+    pub id: String,
+    /// Index of the pane in its window.
+    pub index: usize,
+    /// Width of the pane, in cells.
+    pub width: usize,
+    /// Height of the pane, in cells.
+    pub height: usize,
+    /// Whether this is the active pane of its window.
+    pub active: bool,
+}
+
+impl Pane {
+    /// Parses a single line of `tmux list-panes -F` output produced with [`LIST_PANES_FORMAT`].
+    /// Returns `None` if the line is malformed.
+    pub(crate) fn from_formatted(line: &str) -> Option<Self> {
+        let mut fields = line.split('\x1f');
+
+        let id = fields.next()?.to_string();
+        let index = fields.next()?.parse().ok()?;
+        let width = fields.next()?.parse().ok()?;
+        let height = fields.next()?.parse().ok()?;
+        let active = fields.next()? == "1";
+
+        Some(Self {
+            id,
+            index,
+            width,
+            height,
+            active,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_formatted_parses_pane() {
+        assert_eq!(
+            Pane::from_formatted("%5\x1f1\x1f80\x1f24\x1f1"),
+            Some(Pane {
+                id: "%5".to_string(),
+                index: 1,
+                width: 80,
+                height: 24,
+                active: true,
+            })
+        );
+        assert!(
+            !Pane::from_formatted("%6\x1f2\x1f80\x1f24\x1f0")
+                .unwrap()
+                .active
+        );
+    }
+
+    #[test]
+    fn from_formatted_rejects_malformed_lines() {
+        assert_eq!(Pane::from_formatted("%5\x1f1\x1f80\x1f24"), None);
+        assert_eq!(Pane::from_formatted("%5\x1f1\x1fwide\x1f24\x1f1"), None);
+    }
+}
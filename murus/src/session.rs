@@ -1,3 +1,9 @@
+use std::time::{Duration, SystemTime};
+
+/// Format passed to `tmux list-sessions -F`, fields separated by the ASCII unit separator.
+/// Parsed by [`Session::from_formatted`].
+pub(crate) const LIST_SESSIONS_FORMAT: &str = "#{session_name}\x1f#{session_windows}\x1f#{session_attached}\x1f#{session_created}\x1f#{session_group}";
+
 /// Represents the current state of a given session in tmux.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum State {
@@ -13,6 +19,52 @@ pub struct Session {
     pub name: String,
     pub windows: usize,
     pub state: State,
+    /// Number of clients attached to the session. Only exact when the session was obtained from
+    /// formatted `list-sessions` output, otherwise it is 1 for attached sessions.
+    pub attached_clients: usize,
+    /// Time at which the session was created. Only available when the session was obtained from
+    /// formatted `list-sessions` output.
+    pub created: Option<SystemTime>,
+    /// Name of the session group this session belongs to, if any. Sessions in the same group
+    /// share their windows.
+    pub group: Option<String>,
+}
+
+impl Session {
+    /// Parses a single line of `tmux list-sessions -F` output produced with
+    /// [`LIST_SESSIONS_FORMAT`]. Returns `None` if the line is malformed.
+    pub(crate) fn from_formatted(line: &str) -> Option<Self> {
+        let mut fields = line.split('\x1f');
+
+        let name = fields.next()?.to_string();
+        let windows = fields.next()?.parse().ok()?;
+        let attached_clients = fields.next()?.parse().ok()?;
+        let state = if attached_clients > 0 {
+            State::Attached
+        } else {
+            State::Detached
+        };
+        let created = fields.next().and_then(parse_epoch);
+        let group = fields
+            .next()
+            .filter(|group| !group.is_empty())
+            .map(str::to_string);
+
+        Some(Self {
+            name,
+            windows,
+            state,
+            attached_clients,
+            created,
+            group,
+        })
+    }
+}
+
+/// Parses a Unix epoch (in seconds) as reported by tmux into [`SystemTime`].
+fn parse_epoch(epoch: &str) -> Option<SystemTime> {
+    let secs: u64 = epoch.trim().parse().ok()?;
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
 }
 
 impl From<&str> for Session {
@@ -41,7 +93,65 @@ impl From<&str> for Session {
         Self {
             name: session_name.to_string(),
             windows: window_count,
+            attached_clients: usize::from(state == State::Attached),
             state,
+            created: None,
+            group: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_epoch_of_known_time() {
+        assert_eq!(
+            parse_epoch("1700000000"),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(parse_epoch("not a number"), None);
+    }
+
+    #[test]
+    fn from_formatted_parses_creation_time() {
+        let session = Session::from_formatted("main\x1f3\x1f1\x1f1700000000").unwrap();
+
+        assert_eq!(session.name, "main");
+        assert_eq!(session.windows, 3);
+        assert_eq!(session.state, State::Attached);
+        assert_eq!(
+            session.created,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+    }
+
+    #[test]
+    fn from_formatted_parses_group() {
+        let grouped = Session::from_formatted("work-2\x1f1\x1f0\x1f1700000000\x1fwork").unwrap();
+        let ungrouped = Session::from_formatted("scratch\x1f1\x1f0\x1f1700000000\x1f").unwrap();
+
+        assert_eq!(grouped.group.as_deref(), Some("work"));
+        assert_eq!(grouped.state, State::Detached);
+        assert_eq!(ungrouped.group, None);
+    }
+
+    #[test]
+    fn from_formatted_parses_attached_clients() {
+        let cases = [
+            (0, State::Detached),
+            (1, State::Attached),
+            (2, State::Attached),
+        ];
+
+        for (clients, state) in cases {
+            let session =
+                Session::from_formatted(&format!("main\x1f1\x1f{clients}\x1f1700000000\x1f"))
+                    .unwrap();
+
+            assert_eq!(session.attached_clients, clients);
+            assert_eq!(session.state, state);
         }
     }
 }
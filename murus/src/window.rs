@@ -0,0 +1,81 @@
+/// Format passed to `tmux new-window -P -F`, fields separated by the ASCII unit separator.
+/// Parsed by [`Window::from_formatted`].
+pub(crate) const WINDOW_FORMAT: &str = "#{window_id}\x1f#{window_index}\x1f#{window_name}";
+
+/// Represents information about a single window in tmux.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Window {
+    /// Unique id of the window, e.g. `@3`. Can be used as a target.
+    pub id: String,
+    /// Index of the window in its session.
+    pub index: usize,
+    pub name: String,
+}
+
+impl Window {
+    /// Parses a single line of tmux output produced with [`WINDOW_FORMAT`]. Returns `None` if the
+    /// line is malformed.
+    pub(crate) fn from_formatted(line: &str) -> Option<Self> {
+        let mut fields = line.split('\x1f');
+
+        let id = fields.next()?.to_string();
+        let index = fields.next()?.parse().ok()?;
+        let name = fields.next()?.to_string();
+
+        Some(Self { id, index, name })
+    }
+}
+
+/// Layout of panes in a window, see `select-layout` in tmux(1).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Panes spread out evenly from left to right.
+    EvenHorizontal,
+    /// Panes spread out evenly from top to bottom.
+    EvenVertical,
+    /// A large pane at the top, remaining panes spread out from left to right below it.
+    MainHorizontal,
+    /// A large pane on the left, remaining panes spread out from top to bottom next to it.
+    MainVertical,
+    /// Panes spread out as evenly as possible in both rows and columns.
+    Tiled,
+    /// Raw layout string, e.g. as reported by `#{window_layout}`.
+    Custom(String),
+}
+
+impl Layout {
+    /// Returns the name of the layout as understood by tmux.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Layout::EvenHorizontal => "even-horizontal",
+            Layout::EvenVertical => "even-vertical",
+            Layout::MainHorizontal => "main-horizontal",
+            Layout::MainVertical => "main-vertical",
+            Layout::Tiled => "tiled",
+            Layout::Custom(layout) => layout,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_formatted_parses_window() {
+        assert_eq!(
+            Window::from_formatted("@3\x1f2\x1flogs"),
+            Some(Window {
+                id: "@3".to_string(),
+                index: 2,
+                name: "logs".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn from_formatted_rejects_malformed_lines() {
+        assert_eq!(Window::from_formatted("@3"), None);
+        assert_eq!(Window::from_formatted("@3\x1fsecond\x1flogs"), None);
+    }
+}
@@ -1,12 +1,19 @@
 //! Configuration management for Plux
 
+use std::collections::HashMap;
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use murus::{OptionScope, Tmux};
 
 use crate::error::PluxError;
-use crate::plugin::{DEFAULT_PLUGINS_PATH, DEFAULT_SPEC_PATH, PluginSpecFile};
+use crate::git::Git;
+use crate::plugin::{
+    DEFAULT_HOST, DEFAULT_PLUGINS_PATH, DEFAULT_SPEC_PATH, Layout, PluginSpec, PluginSpecFile,
+    SourceMode,
+};
 
 const DEFAULT_CONFIG_TEMPLATE: &str = r#"# Plux Plugin Configuration
 #
@@ -21,22 +28,155 @@ const DEFAULT_CONFIG_TEMPLATE: &str = r#"# Plux Plugin Configuration
 # my-plugin = { url = "https://github.com/user/plugin", tag = "v1.0.0" }
 # my-plugin = { url = "https://github.com/user/plugin", branch = "main" }
 # my-plugin = { url = "https://github.com/user/plugin", commit = "<hash>" }
+#
+# GitHub plugins can be specified in the shorthand `owner/repo` form:
+# tmux-yank = "tmux-plugins/tmux-yank"
 
 [plugins]
 "#;
 
+/// Default number of attempts at cloning a plugin
+const DEFAULT_CLONE_RETRIES: u32 = 3;
+
+/// Default time a single clone may take before it is killed
+const DEFAULT_CLONE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Number of plugins installed in parallel when the available parallelism can't be determined
+const FALLBACK_MAX_PARALLEL: usize = 4;
+
+/// File format of a plugin spec file, determined by its extension. JSON spec files are always
+/// supported, YAML spec files only when plux is built with the `yaml` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl SpecFormat {
+    /// Determines the format of the spec file from its extension, defaulting to TOML
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => SpecFormat::Json,
+            Some("yaml" | "yml") => SpecFormat::Yaml,
+            _ => SpecFormat::Toml,
+        }
+    }
+
+    /// Returns the human readable name of the format
+    pub fn name(self) -> &'static str {
+        match self {
+            SpecFormat::Toml => "TOML",
+            SpecFormat::Json => "JSON",
+            SpecFormat::Yaml => "YAML",
+        }
+    }
+
+    /// Returns the contents of a newly created spec file without any plugins
+    fn empty_spec(self) -> &'static str {
+        match self {
+            SpecFormat::Toml => DEFAULT_CONFIG_TEMPLATE,
+            SpecFormat::Json => "{\n  \"plugins\": {}\n}\n",
+            SpecFormat::Yaml => "plugins: {}\n",
+        }
+    }
+
+    /// Parses the contents of the spec file at the given path
+    fn parse(self, path: &Path, contents: &str) -> Result<PluginSpecFile, PluxError> {
+        let parse_error =
+            |source: Box<dyn std::error::Error + Send + Sync>| PluxError::ConfigParse {
+                path: path.to_owned(),
+                format: self.name(),
+                source,
+            };
+
+        match self {
+            SpecFormat::Toml => toml::from_str(contents).map_err(|e| parse_error(e.into())),
+            SpecFormat::Json => serde_json::from_str(contents).map_err(|e| parse_error(e.into())),
+            #[cfg(feature = "yaml")]
+            SpecFormat::Yaml => serde_norway::from_str(contents).map_err(|e| parse_error(e.into())),
+            #[cfg(not(feature = "yaml"))]
+            unsupported => Err(PluxError::UnsupportedSpecFormat {
+                path: path.to_owned(),
+                format: unsupported.name(),
+            }),
+        }
+    }
+}
+
+/// Plugin spec merged from a spec file and the spec files it includes, the spec file every plugin
+/// is defined in and the modification time of every loaded spec file
+type LoadedSpec = (
+    PluginSpecFile,
+    HashMap<String, PathBuf>,
+    HashMap<PathBuf, SystemTime>,
+);
+
 /// Configuration for Plux, including paths and plugin specifications
 pub struct Config {
     pub spec_path: PathBuf,
     pub plugins_path: PathBuf,
+    pub layout: Layout,
+    /// Number of attempts at cloning a plugin before giving up
+    pub clone_retries: u32,
+    /// Time a single clone may take before it is killed, or `None` to wait indefinitely
+    pub clone_timeout: Option<Duration>,
+    /// Maximum number of plugins installed in parallel
+    pub max_parallel: usize,
+    /// Whether orphaned plugin directories are removed automatically
+    pub auto_remove_orphans: bool,
+    /// Host used to expand shorthand `owner/repo` plugin URLs
+    pub default_host: String,
+    /// Custom git executable, `git` from `PATH` is used if not set
+    pub git_path: Option<PathBuf>,
+    /// How plugins without a `source_mode` of their own are loaded into tmux
+    pub default_source_mode: SourceMode,
     pub spec: PluginSpecFile,
+    /// Spec file every plugin is defined in, which differs from `spec_path` for plugins of
+    /// included spec files
+    pub plugin_sources: HashMap<String, PathBuf>,
+    /// Modification time of the spec file and every spec file it includes, by canonical path, when
+    /// they were loaded
+    pub spec_modified: HashMap<PathBuf, SystemTime>,
+}
+
+/// Paths overriding the ones configured through tmux options, e.g. given on the command line.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConfigOverrides {
+    /// Plugin spec file used instead of `@plux_toml_path`
+    pub spec_path: Option<PathBuf>,
+    /// Plugins directory used instead of `@plux_plugins_path`
+    pub plugins_path: Option<PathBuf>,
 }
 
 impl Config {
     /// Loads configuration from tmux options and file system
     pub fn load(tmux: &Tmux) -> Result<Self, PluxError> {
-        let spec_path = Self::resolve_spec_path(tmux)?;
-        let plugins_path = Self::resolve_plugins_path(tmux)?;
+        Self::load_with_overrides(tmux, &ConfigOverrides::default())
+    }
+
+    /// Loads configuration like [`Config::load`], but with the paths set in `overrides` taking
+    /// precedence over tmux options.
+    pub fn load_with_overrides(
+        tmux: &Tmux,
+        overrides: &ConfigOverrides,
+    ) -> Result<Self, PluxError> {
+        let spec_path = match &overrides.spec_path {
+            Some(spec_path) => spec_path.clone(),
+            None => Self::resolve_spec_path(tmux)?,
+        };
+        let plugins_path = match &overrides.plugins_path {
+            Some(plugins_path) => plugins_path.clone(),
+            None => Self::resolve_plugins_path(tmux)?,
+        };
+        let layout = Self::resolve_layout(tmux)?;
+        let clone_retries = Self::resolve_clone_retries(tmux)?;
+        let clone_timeout = Self::resolve_clone_timeout(tmux)?;
+        let max_parallel = Self::resolve_max_parallel(tmux)?;
+        let auto_remove_orphans = Self::resolve_auto_remove_orphans(tmux)?;
+        let default_host = Self::resolve_default_host(tmux)?;
+        let git_path = Self::resolve_git_path(tmux)?;
+        let default_source_mode = Self::resolve_default_source_mode(tmux)?;
 
         // Ensure the plugins directory exists
         fs::create_dir_all(&plugins_path).map_err(|e| PluxError::DirectoryCreation {
@@ -44,50 +184,369 @@ impl Config {
             source: e,
         })?;
 
-        let spec = Self::load_spec_file(&spec_path)?;
+        let (spec, plugin_sources, spec_modified) =
+            Self::load_spec_file(&spec_path, &default_host)?;
+        validate_plugin_dirs(&spec, layout)?;
 
         Ok(Config {
             spec_path,
             plugins_path,
+            layout,
+            clone_retries,
+            clone_timeout,
+            max_parallel,
+            auto_remove_orphans,
+            default_host,
+            git_path,
+            default_source_mode,
             spec,
+            plugin_sources,
+            spec_modified,
         })
     }
 
+    /// Adds the plugin to the spec file, preserving the rest of the file including comments.
+    pub fn add_plugin(&mut self, name: &str, spec: PluginSpec) -> Result<(), PluxError> {
+        if self.spec.plugins.contains_key(name) {
+            return Err(PluxError::PluginExists(name.to_string()));
+        }
+
+        let mut resolved = spec.clone();
+        resolved.expand_shorthand_url(&self.default_host);
+
+        if !resolved.has_valid_url() {
+            return Err(PluxError::InvalidPluginUrl {
+                name: name.to_string(),
+                url: spec.url().to_string(),
+            });
+        }
+
+        let spec_path = self.spec_path.clone();
+        let mut document = read_document(&spec_path)?;
+
+        if !document.contains_key("plugins") {
+            document.insert("plugins", toml_edit::table());
+        }
+
+        if let Some(plugins) = document
+            .get_mut("plugins")
+            .and_then(|plugins| plugins.as_table_like_mut())
+        {
+            plugins.insert(name, toml_edit::value(spec.to_toml_value()));
+        }
+
+        self.write_document(&spec_path, &document)?;
+        self.spec.plugins.insert(name.to_string(), resolved);
+        self.plugin_sources.insert(name.to_string(), spec_path);
+
+        Ok(())
+    }
+
+    /// Removes the plugin from the spec file it is defined in, preserving the rest of the file
+    /// including comments. Returns the removed plugin specification.
+    pub fn remove_plugin(&mut self, name: &str) -> Result<PluginSpec, PluxError> {
+        let spec = self
+            .spec
+            .plugins
+            .get(name)
+            .cloned()
+            .ok_or_else(|| PluxError::UnknownPlugin(name.to_string()))?;
+
+        let spec_path = self
+            .plugin_sources
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| self.spec_path.clone());
+        let mut document = read_document(&spec_path)?;
+
+        if let Some(plugins) = document
+            .get_mut("plugins")
+            .and_then(|plugins| plugins.as_table_like_mut())
+        {
+            plugins.remove(name);
+        }
+
+        self.write_document(&spec_path, &document)?;
+        self.spec.plugins.remove(name);
+        self.plugin_sources.remove(name);
+
+        Ok(spec)
+    }
+
+    /// Atomically replaces the spec file at the given path with the document
+    fn write_document(
+        &mut self,
+        path: &Path,
+        document: &toml_edit::DocumentMut,
+    ) -> Result<(), PluxError> {
+        atomic_write(path, document.to_string()).map_err(|e| PluxError::ConfigWrite {
+            path: path.to_owned(),
+            source: e,
+        })?;
+
+        if let Ok(canonical) = fs::canonicalize(path)
+            && let Some(loaded) = self.spec_modified.get_mut(&canonical)
+            && let Ok(current) = modified(path)
+        {
+            *loaded = current;
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the spec file or any spec file it includes was modified since it was loaded
+    pub fn is_stale(&self) -> io::Result<bool> {
+        for (path, loaded) in &self.spec_modified {
+            if modified(path)? != *loaded {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Re-reads the spec file if it was modified since it was loaded. Returns whether the spec
+    /// was reloaded.
+    pub fn reload(&mut self) -> Result<bool, PluxError> {
+        let is_stale = self.is_stale().map_err(|e| PluxError::ConfigRead {
+            path: self.spec_path.clone(),
+            source: e,
+        })?;
+
+        if !is_stale {
+            return Ok(false);
+        }
+
+        let (spec, plugin_sources, spec_modified) =
+            Self::load_spec_file(&self.spec_path, &self.default_host)?;
+        validate_plugin_dirs(&spec, self.layout)?;
+        (self.spec, self.plugin_sources, self.spec_modified) =
+            (spec, plugin_sources, spec_modified);
+
+        Ok(true)
+    }
+
+    /// Returns the directory where the given plugin is installed, according to the layout
+    pub fn plugin_dir(&self, name: &str, spec: &PluginSpec) -> PathBuf {
+        self.layout.plugin_dir(&self.plugins_path, name, spec)
+    }
+
+    /// Returns the git handler used for plugin operations, using the configured git executable
+    pub fn git(&self) -> Git {
+        let git = match &self.git_path {
+            Some(git_path) => Git::default().with_binary(git_path),
+            None => Git::default(),
+        };
+
+        match self.clone_timeout {
+            Some(timeout) => git.with_clone_timeout(timeout),
+            None => git,
+        }
+    }
+
     /// Resolves the plugin spec file path from tmux options or default
     fn resolve_spec_path(tmux: &Tmux) -> Result<PathBuf, PluxError> {
-        let path = tmux
-            .get_option("@plux_toml_path", OptionScope::Global)
-            .unwrap_or_else(|_| DEFAULT_SPEC_PATH.into());
+        let path = tmux.get_option_or("@plux_toml_path", OptionScope::Global, DEFAULT_SPEC_PATH)?;
         expand_path(path)
     }
 
     /// Resolves the plugins directory path from tmux options or default
     fn resolve_plugins_path(tmux: &Tmux) -> Result<PathBuf, PluxError> {
-        let path = tmux
-            .get_option("@plux_plugins_path", OptionScope::Global)
-            .unwrap_or_else(|_| DEFAULT_PLUGINS_PATH.into());
+        let path = tmux.get_option_or(
+            "@plux_plugins_path",
+            OptionScope::Global,
+            DEFAULT_PLUGINS_PATH,
+        )?;
         expand_path(path)
     }
 
-    /// Loads the plugin spec file, creating a default one if it doesn't exist
-    fn load_spec_file(path: &Path) -> Result<PluginSpecFile, PluxError> {
-        match fs::read_to_string(path) {
-            Ok(contents) => toml::from_str(&contents).map_err(|e| PluxError::ConfigParse {
-                path: path.to_owned(),
-                source: e,
-            }),
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                Self::create_default_config(path)?;
-                // Return empty config after creation
-                Ok(PluginSpecFile {
-                    plugins: std::collections::HashMap::new(),
-                })
+    /// Resolves the plugins directory layout from tmux options or default
+    fn resolve_layout(tmux: &Tmux) -> Result<Layout, PluxError> {
+        const OPTION: &str = "@plux_layout";
+
+        match tmux.get_option(OPTION, OptionScope::Global) {
+            Ok(layout) => layout.parse().map_err(PluxError::InvalidLayout),
+            Err(error) => option_default(OPTION, error, Layout::default()),
+        }
+    }
+
+    /// Resolves how plugins are loaded into tmux from tmux options or default
+    fn resolve_default_source_mode(tmux: &Tmux) -> Result<SourceMode, PluxError> {
+        const OPTION: &str = "@plux_default_source_mode";
+
+        match tmux.get_option(OPTION, OptionScope::Global) {
+            Ok(mode) => mode.parse().map_err(PluxError::InvalidSourceMode),
+            Err(error) => option_default(OPTION, error, SourceMode::default()),
+        }
+    }
+
+    /// Resolves the number of clone attempts from tmux options or default
+    fn resolve_clone_retries(tmux: &Tmux) -> Result<u32, PluxError> {
+        const OPTION: &str = "@plux_clone_retries";
+
+        match tmux.get_option_int(OPTION, OptionScope::Global) {
+            Ok(retries) => u32::try_from(retries)
+                .ok()
+                .filter(|retries| *retries > 0)
+                .ok_or_else(|| invalid_option(OPTION, retries)),
+            Err(error) => option_default(OPTION, error, DEFAULT_CLONE_RETRIES),
+        }
+    }
+
+    /// Resolves the clone timeout (in seconds) from tmux options or default. A timeout of 0
+    /// disables it.
+    fn resolve_clone_timeout(tmux: &Tmux) -> Result<Option<Duration>, PluxError> {
+        const OPTION: &str = "@plux_clone_timeout";
+
+        match tmux.get_option_int(OPTION, OptionScope::Global) {
+            Ok(timeout) => u64::try_from(timeout)
+                .map(|secs| (secs > 0).then(|| Duration::from_secs(secs)))
+                .map_err(|_| invalid_option(OPTION, timeout)),
+            Err(error) => option_default(OPTION, error, Some(DEFAULT_CLONE_TIMEOUT)),
+        }
+    }
+
+    /// Resolves the maximum number of parallel installs from tmux options or the number of
+    /// available cores
+    fn resolve_max_parallel(tmux: &Tmux) -> Result<usize, PluxError> {
+        const OPTION: &str = "@plux_max_parallel";
+
+        match tmux.get_option_int(OPTION, OptionScope::Global) {
+            Ok(max_parallel) => usize::try_from(max_parallel)
+                .ok()
+                .filter(|max_parallel| *max_parallel > 0)
+                .ok_or_else(|| invalid_option(OPTION, max_parallel)),
+            Err(error) => {
+                let available = std::thread::available_parallelism()
+                    .map(usize::from)
+                    .unwrap_or(FALLBACK_MAX_PARALLEL);
+                option_default(OPTION, error, available)
+            }
+        }
+    }
+
+    /// Resolves whether orphaned plugins are removed automatically from tmux options or default
+    fn resolve_auto_remove_orphans(tmux: &Tmux) -> Result<bool, PluxError> {
+        const OPTION: &str = "@plux_auto_remove_orphans";
+
+        tmux.get_option_bool(OPTION, OptionScope::Global)
+            .or_else(|error| option_default(OPTION, error, false))
+    }
+
+    /// Resolves the custom git executable from tmux options, if one is set
+    fn resolve_git_path(tmux: &Tmux) -> Result<Option<PathBuf>, PluxError> {
+        const OPTION: &str = "@plux_git_path";
+
+        match tmux.get_option(OPTION, OptionScope::Global) {
+            Ok(path) if !path.trim().is_empty() => expand_path(path.trim().to_string()).map(Some),
+            Ok(_) => Ok(None),
+            Err(error) => option_default(OPTION, error, None),
+        }
+    }
+
+    /// Resolves the host for shorthand plugin URLs from tmux options or default
+    fn resolve_default_host(tmux: &Tmux) -> Result<String, PluxError> {
+        let host = tmux.get_option_or("@plux_default_host", OptionScope::Global, DEFAULT_HOST)?;
+
+        match host.trim() {
+            "" => Ok(DEFAULT_HOST.to_string()),
+            host => Ok(host.to_string()),
+        }
+    }
+
+    /// Loads the plugin spec file and all spec files it includes, creating a default one if it
+    /// doesn't exist. Shorthand plugin URLs are expanded using the given host. Returns the merged
+    /// spec, the spec file every plugin is defined in and the modification time of every loaded
+    /// spec file.
+    fn load_spec_file(path: &Path, default_host: &str) -> Result<LoadedSpec, PluxError> {
+        let mut sources = HashMap::new();
+        let mut loaded = HashMap::new();
+
+        if !path.exists() {
+            Self::create_default_config(path)?;
+            if let Ok(canonical) = fs::canonicalize(path)
+                && let Ok(modified) = modified(path)
+            {
+                loaded.insert(canonical, modified);
+            }
+
+            // Return empty config after creation
+            return Ok((Default::default(), sources, loaded));
+        }
+
+        let spec = Self::load_spec_tree(
+            path,
+            default_host,
+            &mut Vec::new(),
+            &mut sources,
+            &mut loaded,
+        )?;
+
+        Ok((spec, sources, loaded))
+    }
+
+    /// Loads the spec file and, recursively, the spec files it includes. `stack` holds the files
+    /// currently being loaded to detect include cycles, `sources` records the spec file every
+    /// plugin is defined in and `loaded` the modification time of every spec file loaded so far.
+    fn load_spec_tree(
+        path: &Path,
+        default_host: &str,
+        stack: &mut Vec<PathBuf>,
+        sources: &mut HashMap<String, PathBuf>,
+        loaded: &mut HashMap<PathBuf, SystemTime>,
+    ) -> Result<PluginSpecFile, PluxError> {
+        let read_error = |e| PluxError::ConfigRead {
+            path: path.to_owned(),
+            source: e,
+        };
+        let canonical = fs::canonicalize(path).map_err(read_error)?;
+
+        if stack.contains(&canonical) {
+            return Err(PluxError::IncludeCycle(path.to_owned()));
+        }
+
+        // A spec file included by several spec files is only merged the first time
+        if loaded.contains_key(&canonical) {
+            return Ok(PluginSpecFile::default());
+        }
+
+        loaded.insert(canonical.clone(), modified(path).map_err(read_error)?);
+
+        let contents = fs::read_to_string(path).map_err(read_error)?;
+
+        let mut spec = SpecFormat::from_path(path).parse(path, &contents)?;
+
+        for plugin in spec.plugins.values_mut() {
+            plugin.expand_shorthand_url(default_host);
+        }
+
+        validate_urls(&spec)?;
+
+        for name in spec.plugins.keys() {
+            if let Some(existing) = sources.insert(name.clone(), path.to_owned()) {
+                return Err(PluxError::DuplicatePlugin {
+                    name: name.clone(),
+                    path: path.to_owned(),
+                    existing,
+                });
             }
-            Err(e) => Err(PluxError::ConfigRead {
-                path: path.to_owned(),
-                source: e,
-            }),
         }
+
+        stack.push(canonical);
+
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        for include in std::mem::take(&mut spec.include) {
+            let include_path = base_dir.join(expand_path(include)?);
+            let included =
+                Self::load_spec_tree(&include_path, default_host, stack, sources, loaded)?;
+
+            // Plugins defined in more than one spec file were rejected when recording sources
+            spec.plugins.extend(included.plugins);
+        }
+
+        stack.pop();
+
+        Ok(spec)
     }
 
     /// Creates a default config file at the specified path
@@ -99,22 +558,560 @@ impl Config {
             })?;
         }
 
-        fs::write(path, DEFAULT_CONFIG_TEMPLATE).map_err(|e| PluxError::ConfigWrite {
-            path: path.to_owned(),
-            source: e,
+        atomic_write(path, SpecFormat::from_path(path).empty_spec()).map_err(|e| {
+            PluxError::ConfigWrite {
+                path: path.to_owned(),
+                source: e,
+            }
         })?;
 
-        println!("Created default config file at {}", path.display());
-        println!("Add your plugins to this file and reload tmux configuration.");
+        log::info!("Created default config file at {}", path.display());
+        log::info!("Add your plugins to this file and reload tmux configuration.");
 
         Ok(())
     }
 }
 
-/// Expands ~ and $HOME in paths
-fn expand_path(mut path: String) -> Result<PathBuf, PluxError> {
-    let home = std::env::var("HOME")?;
-    path = path.replace("$HOME", &home);
-    path = path.replace('~', &home);
-    Ok(PathBuf::from(path))
+/// Reads the spec file at the given path as an editable TOML document
+fn read_document(path: &Path) -> Result<toml_edit::DocumentMut, PluxError> {
+    if SpecFormat::from_path(path) != SpecFormat::Toml {
+        return Err(PluxError::UneditableSpecFile(path.to_owned()));
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| PluxError::ConfigRead {
+        path: path.to_owned(),
+        source: e,
+    })?;
+
+    contents.parse().map_err(|e| PluxError::ConfigEdit {
+        path: path.to_owned(),
+        source: e,
+    })
+}
+
+/// Checks that no two plugins in the spec are installed into the same directory, which happens
+/// when their names only differ in characters replaced in directory names, or when they share a
+/// URL in the nested layout
+fn validate_plugin_dirs(spec: &PluginSpecFile, layout: Layout) -> Result<(), PluxError> {
+    let mut plugins: Vec<_> = spec.plugins.iter().collect();
+    plugins.sort_by_key(|(name, _)| name.as_str());
+
+    let mut dirs: HashMap<PathBuf, &String> = HashMap::new();
+
+    for (name, plugin) in plugins {
+        let dir = layout.relative_dir(name, plugin);
+
+        if let Some(existing) = dirs.insert(dir.clone(), name) {
+            return Err(PluxError::DuplicatePluginDir {
+                a: existing.clone(),
+                b: name.clone(),
+                dir,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every plugin in the spec has a URL that can be cloned
+fn validate_urls(spec: &PluginSpecFile) -> Result<(), PluxError> {
+    let mut plugins: Vec<_> = spec.plugins.iter().collect();
+    plugins.sort_by_key(|(name, _)| name.as_str());
+
+    match plugins
+        .into_iter()
+        .find(|(_, plugin)| !plugin.has_valid_url())
+    {
+        Some((name, plugin)) => Err(PluxError::InvalidPluginUrl {
+            name: name.clone(),
+            url: plugin.url().to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Writes the contents into a temporary file next to the given path and renames it into place,
+/// so the file is never left partially written. Symlinks are followed, so a symlinked file is
+/// updated instead of being replaced. If the file can't be renamed into place, e.g. because it
+/// lives on a different filesystem, it is copied over instead.
+pub fn atomic_write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    let tmp_path = path.with_file_name(file_name);
+
+    let result = write_synced(&tmp_path, contents.as_ref()).and_then(|_| {
+        match fs::rename(&tmp_path, &path) {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                fs::copy(&tmp_path, &path).map(|_| ())
+            }
+            result => result,
+        }
+    });
+
+    // The temporary file is gone after a successful rename, so this only cleans up after errors
+    // and copies
+    let _ = fs::remove_file(&tmp_path);
+
+    result
+}
+
+/// Writes the contents to the file and flushes them to disk
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Returns the modification time of the file at the given path
+fn modified(path: &Path) -> io::Result<SystemTime> {
+    fs::metadata(path)?.modified()
+}
+
+/// Returns the default value of an option that is not set. Malformed values and failures of
+/// tmux itself are reported as errors.
+fn option_default<T>(
+    option: &'static str,
+    error: murus::Error,
+    default: T,
+) -> Result<T, PluxError> {
+    match error {
+        murus::Error::OptionNotFound(_) => Ok(default),
+        murus::Error::OptionParse { value, .. } => Err(PluxError::InvalidOption { option, value }),
+        error => Err(error.into()),
+    }
+}
+
+/// Error for an option whose value is well-formed, but out of range
+fn invalid_option(option: &'static str, value: impl ToString) -> PluxError {
+    PluxError::InvalidOption {
+        option,
+        value: value.to_string(),
+    }
+}
+
+/// Expands a leading `~` and environment variables referenced as `$VAR` or `${VAR}` in paths,
+/// e.g. `$XDG_CONFIG_HOME/tmux/plux.toml`. Like in shells, `~` is only expanded as a bare `~` or
+/// at the start of `~/...`, other `~` (including `~user`) are kept as is.
+fn expand_path(path: String) -> Result<PathBuf, PluxError> {
+    expand_with(path, |variable| std::env::var(variable))
+}
+
+/// Expands the path like [`expand_path`], looking up variables with the given function
+fn expand_with(
+    path: String,
+    lookup: impl Fn(&str) -> Result<String, std::env::VarError>,
+) -> Result<PathBuf, PluxError> {
+    let var = |variable: &str| {
+        lookup(variable).map_err(|e| PluxError::PathExpansion {
+            path: path.clone(),
+            variable: variable.to_string(),
+            source: e,
+        })
+    };
+
+    let mut expanded = String::with_capacity(path.len());
+    let mut rest = path.as_str();
+
+    if rest == "~" || rest.starts_with("~/") {
+        expanded.push_str(&var("HOME")?);
+        rest = &rest[1..];
+    }
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let (variable, remaining) = match rest.strip_prefix('{') {
+            Some(braced) => match braced.split_once('}') {
+                Some((variable, remaining)) => (variable, remaining),
+                None => ("", rest),
+            },
+            None => {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                rest.split_at(end)
+            }
+        };
+
+        if variable.is_empty() {
+            // Not a variable reference, keep the '$' as is
+            expanded.push('$');
+            continue;
+        }
+
+        expanded.push_str(&var(variable)?);
+        rest = remaining;
+    }
+
+    expanded.push_str(rest);
+
+    Ok(PathBuf::from(expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::git::GitError;
+    use crate::test_util::{TempDir, config, init_repo};
+
+    /// Moves the modification time of the file into the future, as if it was just edited
+    fn touch(path: &Path) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+    }
+
+    /// Loads the spec of the configuration and every spec file it includes from disk
+    fn load(config: &mut Config) {
+        (config.spec, config.plugin_sources, config.spec_modified) =
+            Config::load_spec_file(&config.spec_path, DEFAULT_HOST).unwrap();
+    }
+
+    #[test]
+    fn spec_is_stale_after_modification() {
+        let dir = TempDir::new();
+        let mut config = config(dir.path(), "[plugins]\n");
+        load(&mut config);
+
+        assert!(!config.is_stale().unwrap());
+
+        touch(&config.spec_path);
+
+        assert!(config.is_stale().unwrap());
+    }
+
+    #[test]
+    fn reload_reads_modified_spec() {
+        let dir = TempDir::new();
+        let mut config = config(dir.path(), "[plugins]\n");
+        load(&mut config);
+
+        assert!(!config.reload().unwrap());
+
+        fs::write(
+            &config.spec_path,
+            "[plugins]\ntmux-yank = \"https://github.com/tmux-plugins/tmux-yank\"\n",
+        )
+        .unwrap();
+        touch(&config.spec_path);
+
+        assert!(config.reload().unwrap());
+        assert!(config.spec.plugins.contains_key("tmux-yank"));
+        assert!(!config.is_stale().unwrap());
+    }
+
+    #[test]
+    fn remove_plugin_preserves_rest_of_spec_file() {
+        let dir = TempDir::new();
+        let mut config = config(
+            dir.path(),
+            "# my plugins\n[plugins]\ntmux-yank = \"https://github.com/tmux-plugins/tmux-yank\"\n",
+        );
+
+        let removed = config.remove_plugin("tmux-yank").unwrap();
+
+        assert_eq!(removed.url(), "https://github.com/tmux-plugins/tmux-yank");
+        assert_eq!(
+            fs::read_to_string(&config.spec_path).unwrap(),
+            "# my plugins\n[plugins]\n"
+        );
+        assert!(!config.spec.plugins.contains_key("tmux-yank"));
+        assert!(matches!(
+            config.remove_plugin("tmux-yank"),
+            Err(PluxError::UnknownPlugin(name)) if name == "tmux-yank"
+        ));
+    }
+
+    #[test]
+    fn spec_is_stale_after_modifying_included_file() {
+        let dir = TempDir::new();
+        let work_path = dir.path().join("work.toml");
+        fs::write(&work_path, "[plugins]\n").unwrap();
+        let mut config = config(dir.path(), "include = [\"work.toml\"]\n[plugins]\n");
+        load(&mut config);
+
+        assert!(!config.is_stale().unwrap());
+
+        fs::write(
+            &work_path,
+            "[plugins]\ntmux-yank = \"https://github.com/tmux-plugins/tmux-yank\"\n",
+        )
+        .unwrap();
+        touch(&work_path);
+
+        assert!(config.is_stale().unwrap());
+        assert!(config.reload().unwrap());
+        assert!(config.spec.plugins.contains_key("tmux-yank"));
+    }
+
+    #[test]
+    fn expand_path_with_variables() {
+        let variables = HashMap::from([("XDG_CONFIG_HOME", "/home/user/.config")]);
+        let expand = |path: &str| {
+            expand_with(path.to_string(), |variable| {
+                variables
+                    .get(variable)
+                    .map(|value| value.to_string())
+                    .ok_or(std::env::VarError::NotPresent)
+            })
+        };
+
+        assert_eq!(
+            expand("$XDG_CONFIG_HOME/tmux/plux.toml").unwrap(),
+            PathBuf::from("/home/user/.config/tmux/plux.toml")
+        );
+        assert_eq!(
+            expand("${XDG_CONFIG_HOME}/tmux/plux.toml").unwrap(),
+            PathBuf::from("/home/user/.config/tmux/plux.toml")
+        );
+        assert_eq!(
+            expand("/plugins/$/cost").unwrap(),
+            PathBuf::from("/plugins/$/cost")
+        );
+        assert!(matches!(
+            expand("$HOME/plux.toml"),
+            Err(PluxError::PathExpansion { variable, .. }) if variable == "HOME"
+        ));
+    }
+
+    #[test]
+    fn expand_path_with_undefined_variable() {
+        let error = expand_path("$PLUX_TEST_UNDEFINED/plux.toml".to_string()).unwrap_err();
+
+        assert!(matches!(
+            &error,
+            PluxError::PathExpansion { variable, .. } if variable == "PLUX_TEST_UNDEFINED"
+        ));
+        assert!(error.to_string().contains("PLUX_TEST_UNDEFINED"), "{error}");
+    }
+
+    #[test]
+    fn add_plugin_preserves_comments() {
+        let dir = TempDir::new();
+        let mut config = config(dir.path(), "# my plugins\n[plugins]\n");
+
+        config
+            .add_plugin(
+                "tmux-yank",
+                PluginSpec::Url("tmux-plugins/tmux-yank".to_string()),
+            )
+            .unwrap();
+
+        let contents = fs::read_to_string(&config.spec_path).unwrap();
+        assert!(contents.starts_with("# my plugins\n"), "{contents}");
+
+        let (spec, _, _) = Config::load_spec_file(&config.spec_path, DEFAULT_HOST).unwrap();
+        assert_eq!(
+            spec.plugins["tmux-yank"].url(),
+            "https://github.com/tmux-plugins/tmux-yank"
+        );
+        assert_eq!(
+            config.spec.plugins["tmux-yank"].url(),
+            "https://github.com/tmux-plugins/tmux-yank"
+        );
+        assert!(matches!(
+            config.add_plugin("tmux-yank", PluginSpec::Url("tmux-plugins/tmux-yank".to_string())),
+            Err(PluxError::PluginExists(name)) if name == "tmux-yank"
+        ));
+    }
+
+    #[test]
+    fn git_uses_configured_binary() {
+        let dir = TempDir::new();
+        let mut config = config(dir.path(), "[plugins]\n");
+
+        let system_git = std::env::split_paths(&std::env::var_os("PATH").unwrap())
+            .map(|dir| dir.join("git"))
+            .find(|path| path.is_file())
+            .unwrap();
+        let custom_git = dir.path().join("custom-git");
+        std::os::unix::fs::symlink(system_git, &custom_git).unwrap();
+
+        let repo = dir.path().join("repo");
+        init_repo(&repo);
+
+        config.git_path = Some(custom_git);
+        assert!(config.git().for_repo(&repo).current_commit().is_ok());
+
+        config.git_path = Some(dir.path().join("missing-git"));
+        assert!(matches!(
+            config.git().for_repo(&repo).current_commit(),
+            Err(GitError::IoError(error)) if error.kind() == io::ErrorKind::NotFound
+        ));
+    }
+
+    #[test]
+    fn load_spec_file_merges_includes() {
+        let dir = TempDir::new();
+        let spec_path = dir.path().join("plux.toml");
+        let work_path = dir.path().join("work.toml");
+        fs::write(
+            &spec_path,
+            "include = [\"work.toml\"]\n[plugins]\ntmux-yank = \"tmux-plugins/tmux-yank\"\n",
+        )
+        .unwrap();
+        fs::write(
+            &work_path,
+            "[plugins]\ntmux-sensible = \"tmux-plugins/tmux-sensible\"\n",
+        )
+        .unwrap();
+
+        let (spec, sources, _) = Config::load_spec_file(&spec_path, DEFAULT_HOST).unwrap();
+
+        assert_eq!(
+            spec.plugins["tmux-sensible"].url(),
+            "https://github.com/tmux-plugins/tmux-sensible"
+        );
+        assert_eq!(sources["tmux-yank"], spec_path);
+        assert_eq!(sources["tmux-sensible"], work_path);
+    }
+
+    #[test]
+    fn load_spec_file_detects_include_cycle() {
+        let dir = TempDir::new();
+        let spec_path = dir.path().join("plux.toml");
+        fs::write(&spec_path, "include = [\"work.toml\"]\n").unwrap();
+        fs::write(dir.path().join("work.toml"), "include = [\"plux.toml\"]\n").unwrap();
+
+        assert!(matches!(
+            Config::load_spec_file(&spec_path, DEFAULT_HOST),
+            Err(PluxError::IncludeCycle(path)) if path == dir.path().join("plux.toml")
+        ));
+    }
+
+    #[test]
+    fn load_spec_file_merges_diamond_includes() {
+        let dir = TempDir::new();
+        let spec_path = dir.path().join("plux.toml");
+        let common_path = dir.path().join("common.toml");
+        fs::write(&spec_path, "include = [\"a.toml\", \"b.toml\"]\n").unwrap();
+        fs::write(dir.path().join("a.toml"), "include = [\"common.toml\"]\n").unwrap();
+        fs::write(dir.path().join("b.toml"), "include = [\"common.toml\"]\n").unwrap();
+        fs::write(
+            &common_path,
+            "[plugins]\ntmux-yank = \"tmux-plugins/tmux-yank\"\n",
+        )
+        .unwrap();
+
+        let (spec, sources, _) = Config::load_spec_file(&spec_path, DEFAULT_HOST).unwrap();
+
+        assert_eq!(spec.plugins.len(), 1);
+        assert_eq!(sources["tmux-yank"], common_path);
+    }
+
+    #[test]
+    fn load_spec_file_rejects_plugin_defined_twice() {
+        let dir = TempDir::new();
+        let spec_path = dir.path().join("plux.toml");
+        fs::write(
+            &spec_path,
+            "include = [\"work.toml\"]\n[plugins]\ntmux-yank = \"tmux-plugins/tmux-yank\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("work.toml"),
+            "[plugins]\ntmux-yank = \"tmux-plugins/tmux-yank\"\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            Config::load_spec_file(&spec_path, DEFAULT_HOST),
+            Err(PluxError::DuplicatePlugin { name, path, existing })
+                if name == "tmux-yank"
+                    && path == dir.path().join("work.toml")
+                    && existing == spec_path
+        ));
+    }
+
+    #[test]
+    fn remove_plugin_from_included_file() {
+        let dir = TempDir::new();
+        let work_path = dir.path().join("work.toml");
+        fs::write(
+            &work_path,
+            "# work plugins\n[plugins]\ntmux-sensible = \"tmux-plugins/tmux-sensible\"\n",
+        )
+        .unwrap();
+        let mut config = config(dir.path(), "include = [\"work.toml\"]\n[plugins]\n");
+        load(&mut config);
+
+        let removed = config.remove_plugin("tmux-sensible").unwrap();
+
+        assert_eq!(
+            removed.url(),
+            "https://github.com/tmux-plugins/tmux-sensible"
+        );
+        assert_eq!(
+            fs::read_to_string(&work_path).unwrap(),
+            "# work plugins\n[plugins]\n"
+        );
+        assert!(!config.spec.plugins.contains_key("tmux-sensible"));
+        assert!(matches!(
+            config.remove_plugin("tmux-sensible"),
+            Err(PluxError::UnknownPlugin(name)) if name == "tmux-sensible"
+        ));
+    }
+
+    #[test]
+    fn expand_path_with_tilde() {
+        let home = std::env::var("HOME").unwrap();
+
+        assert_eq!(
+            expand_path("~/foo".to_string()).unwrap(),
+            PathBuf::from(format!("{home}/foo"))
+        );
+        assert_eq!(expand_path("~".to_string()).unwrap(), PathBuf::from(&home));
+        assert_eq!(
+            expand_path("/a/b~c".to_string()).unwrap(),
+            PathBuf::from("/a/b~c")
+        );
+        assert_eq!(
+            expand_path("~user/foo".to_string()).unwrap(),
+            PathBuf::from("~user/foo")
+        );
+    }
+
+    #[test]
+    fn validate_plugin_dirs_detects_colliding_names() {
+        let spec: PluginSpecFile = toml::from_str(
+            r#"
+            [plugins]
+            "owner/repo" = "https://github.com/owner/repo"
+            owner_repo = "https://github.com/other/repo"
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            validate_plugin_dirs(&spec, Layout::Flat),
+            Err(PluxError::DuplicatePluginDir { a, b, dir })
+                if a == "owner/repo" && b == "owner_repo" && dir == Path::new("owner_repo")
+        ));
+        assert!(validate_plugin_dirs(&spec, Layout::Nested).is_ok());
+    }
+
+    #[test]
+    fn validate_plugin_dirs_detects_colliding_urls() {
+        let spec: PluginSpecFile = toml::from_str(
+            r#"
+            [plugins]
+            yank = "https://github.com/tmux-plugins/tmux-yank"
+            tmux-yank = "git@github.com:tmux-plugins/tmux-yank.git"
+            "#,
+        )
+        .unwrap();
+
+        assert!(validate_plugin_dirs(&spec, Layout::Flat).is_ok());
+        assert!(matches!(
+            validate_plugin_dirs(&spec, Layout::Nested),
+            Err(PluxError::DuplicatePluginDir { .. })
+        ));
+    }
 }
@@ -0,0 +1,101 @@
+//! Disk usage helpers for Plux
+
+use std::fs;
+use std::path::Path;
+
+/// Computes the total size in bytes of all files in the directory, recursively. Unreadable
+/// entries are skipped with a warning.
+pub fn dir_size(path: &Path) -> u64 {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::warn!("Skipping {}: {error}", path.display());
+            return 0;
+        }
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            // NOTE: symlinks are not followed, only the link itself is counted
+            Ok(_) => entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Sorts the entries by their size, largest first. Entries without a size are listed last, the
+/// sort is stable otherwise.
+pub fn sort_largest_first<T>(entries: &mut [T], size: impl Fn(&T) -> Option<u64>) {
+    entries.sort_by_key(|entry| std::cmp::Reverse(size(entry)));
+}
+
+/// Formats a size in bytes in a human readable form, e.g. `1.5 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[0])
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn dir_size_counts_files_recursively() {
+        let dir = TempDir::new();
+        fs::create_dir_all(dir.path().join("large/.git/objects")).unwrap();
+        fs::create_dir_all(dir.path().join("small")).unwrap();
+        fs::write(dir.path().join("large/plugin.tmux"), [0; 100]).unwrap();
+        fs::write(dir.path().join("large/.git/objects/pack"), [0; 50]).unwrap();
+        fs::write(dir.path().join("small/plugin.tmux"), [0; 10]).unwrap();
+
+        assert_eq!(dir_size(&dir.path().join("large")), 150);
+        assert_eq!(dir_size(&dir.path().join("small")), 10);
+        assert_eq!(dir_size(dir.path()), 160);
+        assert_eq!(dir_size(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn sort_largest_first_lists_unknown_sizes_last() {
+        let mut entries = [
+            ("a", Some(10)),
+            ("b", None),
+            ("c", Some(150)),
+            ("d", Some(10)),
+        ];
+
+        sort_largest_first(&mut entries, |(_, size)| *size);
+
+        assert_eq!(
+            entries,
+            [
+                ("c", Some(150)),
+                ("a", Some(10)),
+                ("d", Some(10)),
+                ("b", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn format_size_units() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
@@ -29,15 +29,94 @@ pub enum PluxError {
         source: std::io::Error,
     },
 
-    #[error("Invalid TOML syntax in {path}: {source}")]
+    #[error("Invalid {format} syntax in {path}: {source}")]
     ConfigParse {
         path: PathBuf,
+        format: &'static str,
         #[source]
-        source: toml::de::Error,
+        source: Box<dyn std::error::Error + Send + Sync>,
     },
 
-    #[error("Could not expand path: {0}")]
-    PathExpansion(#[from] std::env::VarError),
+    #[error("Cannot read {path}: plux was built without {format} support")]
+    UnsupportedSpecFormat { path: PathBuf, format: &'static str },
+
+    #[error("Cannot edit {0}: only TOML spec files can be edited")]
+    UneditableSpecFile(PathBuf),
+
+    #[error("Could not edit config at {path}: {source}")]
+    ConfigEdit {
+        path: PathBuf,
+        #[source]
+        source: toml_edit::TomlError,
+    },
+
+    #[error("Could not remove plugin at {path}: {source}")]
+    PluginRemoval {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Could not write state file at {path}: {source}")]
+    StateWrite {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Could not serialize TOML: {0}")]
+    StateSerialize(#[from] toml::ser::Error),
+
+    #[error("Invalid plugins layout '{0}', expected 'flat' or 'nested'")]
+    InvalidLayout(String),
+
+    #[error("Invalid source mode '{0}', expected 'auto', 'source_file' or 'run_shell'")]
+    InvalidSourceMode(String),
+
+    #[error("Invalid URL '{url}' for plugin '{name}'")]
+    InvalidPluginUrl { name: String, url: String },
+
+    #[error("Invalid value '{value}' for option {option}")]
+    InvalidOption { option: &'static str, value: String },
+
+    #[error("Could not expand ${variable} in path '{path}': {source}")]
+    PathExpansion {
+        path: String,
+        variable: String,
+        #[source]
+        source: std::env::VarError,
+    },
+
+    #[error("Plugin '{0}' is not in the plugin spec file")]
+    UnknownPlugin(String),
+
+    #[error("Plugin '{0}' is already in the plugin spec file")]
+    PluginExists(String),
+
+    #[error("Plugin '{name}' in {path} is already defined in {existing}")]
+    DuplicatePlugin {
+        name: String,
+        path: PathBuf,
+        existing: PathBuf,
+    },
+
+    #[error("Plugins '{a}' and '{b}' would both be installed into {}", .dir.display())]
+    DuplicatePluginDir { a: String, b: String, dir: PathBuf },
+
+    #[error("Spec file {0} is part of an include cycle")]
+    IncludeCycle(PathBuf),
+
+    #[error("{0} plugin(s) failed to install or load")]
+    PluginsFailed(usize),
+
+    #[error("{0} plugin(s) do not match the plugin spec")]
+    VerificationFailed(usize),
+
+    #[error("{0} check(s) failed")]
+    ChecksFailed(usize),
+
+    #[error("Git error: {0}")]
+    Git(#[from] crate::git::GitError),
 
     #[error("Plugin installation error: {0}")]
     PluginInstall(#[from] crate::plugin::InstallError),
@@ -45,3 +124,14 @@ pub enum PluxError {
     #[error("Tmux error: {0}")]
     Tmux(#[from] murus::Error),
 }
+
+impl PluxError {
+    /// Exit code of the plux process when it fails with this error: `2` if some plugins failed
+    /// or don't match the spec while the rest were processed, `1` for any other error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PluxError::PluginsFailed(_) | PluxError::VerificationFailed(_) => 2,
+            _ => 1,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Export of the plugin specification into formats usable without plux
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::git::Git;
+use crate::lock::LockFile;
+use crate::plugin::{PluginSpec, Version};
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Standalone POSIX shell script that clones and checks out every plugin.
+    Sh,
+    /// TPM `@plugin` lines to be pasted into `~/.tmux.conf`.
+    Tpm,
+}
+
+/// Exports the plugin specification in the given format. Plugins are pinned to the commit
+/// recorded in `plux.lock`, or to the commit currently checked out in the plugins directory if
+/// they are not locked.
+pub fn export(config: &Config, format: ExportFormat) -> String {
+    let mut plugins: Vec<_> = config.spec.plugins.iter().collect();
+    plugins.sort_by_key(|(name, _)| name.as_str());
+
+    match format {
+        ExportFormat::Sh => {
+            let lock = LockFile::load(&LockFile::path(&config.spec_path)).unwrap_or_else(|error| {
+                log::warn!("Ignoring lockfile: {error}");
+                LockFile::default()
+            });
+
+            export_sh(config, &lock, &plugins)
+        }
+        ExportFormat::Tpm => export_tpm(&plugins),
+    }
+}
+
+fn export_sh(config: &Config, lock: &LockFile, plugins: &[(&String, &PluginSpec)]) -> String {
+    let mut script = String::from(
+        r#"#!/bin/sh
+# Generated by plux. Installs tmux plugins without plux.
+set -e
+
+"#,
+    );
+    let _ = writeln!(
+        script,
+        "PLUGINS_DIR=${{PLUX_PLUGINS_PATH:-{}}}",
+        shell_quote(&config.plugins_path.to_string_lossy())
+    );
+    let _ = writeln!(script, "mkdir -p \"$PLUGINS_DIR\"");
+
+    for (name, plugin) in plugins {
+        let relative_dir = config.layout.relative_dir(name, plugin);
+        let dest = format!(
+            "\"$PLUGINS_DIR\"/{}",
+            shell_quote(&relative_dir.to_string_lossy())
+        );
+
+        let _ = writeln!(script);
+        let _ = writeln!(script, "git clone {} {dest}", shell_quote(plugin.url()));
+
+        let plugin_dir = config.plugin_dir(name, plugin);
+        if let Some(version) = resolve_ref(&config.git(), lock, &plugin_dir, name, plugin) {
+            let _ = writeln!(script, "git -C {dest} checkout {}", shell_quote(&version));
+        }
+    }
+
+    script
+}
+
+fn export_tpm(plugins: &[(&String, &PluginSpec)]) -> String {
+    let mut lines = String::new();
+
+    for (_, plugin) in plugins {
+        let reference = match plugin {
+            PluginSpec::Full(full) => match &full.tag_or_commit {
+                Some(Version::Tag(reference) | Version::Branch(reference)) => {
+                    format!("#{}", reference.trim())
+                }
+                // TPM has no way of pinning a commit
+                Some(Version::Commit(_)) | None => String::new(),
+            },
+            PluginSpec::Url(_) => String::new(),
+        };
+
+        let _ = writeln!(lines, "set -g @plugin '{}{reference}'", plugin.url());
+    }
+
+    lines
+}
+
+/// Resolves the git reference the exported script should check out. Prefers the locked commit,
+/// then the commit currently checked out in an installed plugin, and falls back to the version
+/// from the specification.
+fn resolve_ref(
+    git: &Git,
+    lock: &LockFile,
+    plugin_dir: &Path,
+    name: &str,
+    plugin: &PluginSpec,
+) -> Option<String> {
+    if let Some(commit) = lock.locked_commit(name, plugin) {
+        return Some(commit.to_string());
+    }
+
+    if plugin_dir.is_dir()
+        && let Ok(commit) = git.for_repo(plugin_dir).current_commit()
+    {
+        return Some(commit);
+    }
+
+    match plugin.version() {
+        Some(Version::Tag(reference) | Version::Commit(reference)) => {
+            Some(reference.trim().to_string())
+        }
+        Some(Version::Branch(branch)) => {
+            log::warn!("{name} is neither locked nor installed, exporting branch '{branch}'");
+            Some(branch.trim().to_string())
+        }
+        None => {
+            log::warn!("{name} is neither locked nor installed, exporting its default branch");
+            None
+        }
+    }
+}
+
+/// Quotes the value so it is passed verbatim as a single shell word.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::LockedPlugin;
+    use crate::test_util::{TempDir, config, git, init_repo};
+
+    #[test]
+    fn export_sh_checks_out_installed_commits() {
+        let dir = TempDir::new();
+        let config = config(
+            dir.path(),
+            r#"
+            [plugins]
+            tmux-yank = "https://github.com/tmux-plugins/tmux-yank"
+            tmux-sensible = { url = "https://github.com/tmux-plugins/tmux-sensible", tag = "v3.0.0" }
+            "#,
+        );
+        let yank_dir = config.plugins_path.join("tmux-yank");
+        init_repo(&yank_dir);
+        let commit = git(&yank_dir, &["rev-parse", "HEAD"]);
+
+        let script = export(&config, ExportFormat::Sh);
+
+        assert!(script.contains(&format!(
+            "PLUGINS_DIR=${{PLUX_PLUGINS_PATH:-'{}'}}\n",
+            config.plugins_path.display()
+        )));
+        assert!(script.contains(
+            "git clone 'https://github.com/tmux-plugins/tmux-sensible' \"$PLUGINS_DIR\"/'tmux-sensible'\n\
+             git -C \"$PLUGINS_DIR\"/'tmux-sensible' checkout 'v3.0.0'\n"
+        ));
+        assert!(script.contains(&format!(
+            "git clone 'https://github.com/tmux-plugins/tmux-yank' \"$PLUGINS_DIR\"/'tmux-yank'\n\
+             git -C \"$PLUGINS_DIR\"/'tmux-yank' checkout '{commit}'\n"
+        )));
+    }
+
+    #[test]
+    fn export_sh_checks_out_locked_commits() {
+        let dir = TempDir::new();
+        let config = config(
+            dir.path(),
+            r#"
+            [plugins]
+            tmux-yank = "https://github.com/tmux-plugins/tmux-yank"
+            "#,
+        );
+        init_repo(&config.plugins_path.join("tmux-yank"));
+
+        let mut lock = LockFile::default();
+        let yank = &config.spec.plugins["tmux-yank"];
+        lock.plugins.insert(
+            "tmux-yank".to_string(),
+            LockedPlugin::new(yank, "1a2b3c".to_string()),
+        );
+
+        let plugins: Vec<_> = config.spec.plugins.iter().collect();
+        let script = export_sh(&config, &lock, &plugins);
+
+        assert!(script.contains(
+            "git clone 'https://github.com/tmux-plugins/tmux-yank' \"$PLUGINS_DIR\"/'tmux-yank'\n\
+             git -C \"$PLUGINS_DIR\"/'tmux-yank' checkout '1a2b3c'\n"
+        ));
+    }
+}
@@ -1,7 +1,11 @@
 //! Git operations abstraction for Plux
 
+use std::collections::HashMap;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
 
 /// Errors that can occur during git operations
 #[derive(Debug, thiserror::Error)]
@@ -9,60 +13,240 @@ pub enum GitError {
     #[error("Git command '{command}' failed:\n{stderr}")]
     CommandFailed { command: String, stderr: String },
 
+    #[error("Git command '{command}' timed out after {}s", .timeout.as_secs())]
+    Timeout { command: String, timeout: Duration },
+
+    #[error("Could not determine the default branch of the repository")]
+    NoDefaultBranch,
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
 
+impl GitError {
+    /// Whether the error is likely caused by a flaky network rather than e.g. a missing
+    /// repository or failed authentication, so retrying might succeed
+    pub fn is_transient(&self) -> bool {
+        const TRANSIENT_PATTERNS: [&str; 7] = [
+            "timed out",
+            "connection reset",
+            "connection refused",
+            "could not resolve host",
+            "early eof",
+            "the remote end hung up unexpectedly",
+            "rpc failed",
+        ];
+
+        match self {
+            GitError::CommandFailed { stderr, .. } => {
+                let stderr = stderr.to_lowercase();
+                TRANSIENT_PATTERNS
+                    .iter()
+                    .any(|pattern| stderr.contains(pattern))
+            }
+            // NOTE: retrying a stuck clone would only keep the user waiting even longer
+            GitError::Timeout { .. } | GitError::NoDefaultBranch | GitError::IoError(_) => false,
+        }
+    }
+}
+
 /// Git operations handler
-#[derive(Default)]
+#[derive(Debug, Clone)]
 pub struct Git {
+    binary: PathBuf,
+    env: HashMap<String, String>,
     repo_path: Option<PathBuf>,
+    clone_timeout: Option<Duration>,
+}
+
+impl Default for Git {
+    fn default() -> Self {
+        Self {
+            binary: PathBuf::from("git"),
+            env: HashMap::new(),
+            repo_path: None,
+            clone_timeout: None,
+        }
+    }
 }
 
 impl Git {
     /// Create a Git instance for an existing repository
     pub fn in_repo(path: impl Into<PathBuf>) -> Self {
+        Self::default().for_repo(path)
+    }
+
+    /// Creates a Git instance for an existing repository, using the same git executable and
+    /// environment as this one
+    pub fn for_repo(&self, path: impl Into<PathBuf>) -> Self {
         Self {
             repo_path: Some(path.into()),
+            ..self.clone()
         }
     }
 
-    /// Creates a git command with the appropriate working directory
+    /// Uses the given git executable instead of the `git` found in `PATH`
+    pub fn with_binary(mut self, binary: impl Into<PathBuf>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+
+    /// Sets additional environment variables for every git command, e.g. `GIT_SSH_COMMAND`
+    pub fn with_env(mut self, env: HashMap<String, String>) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Kills clones that don't finish within `timeout`, failing them with [`GitError::Timeout`].
+    /// By default clones are waited on indefinitely.
+    pub fn with_clone_timeout(mut self, timeout: Duration) -> Self {
+        self.clone_timeout = Some(timeout);
+        self
+    }
+
+    /// Creates a git command with the appropriate working directory. Git does not look for a
+    /// repository above the working directory, so a plugin directory without `.git` (e.g. after
+    /// `clean --strip-history`) fails instead of silently using an enclosing repository.
     fn command(&self) -> Command {
-        let mut cmd = Command::new("git");
+        let mut cmd = Command::new(&self.binary);
         if let Some(path) = &self.repo_path {
             cmd.current_dir(path);
+            if let Some(parent) = path.parent().filter(|parent| parent.is_absolute()) {
+                cmd.env("GIT_CEILING_DIRECTORIES", parent);
+            }
         }
+        cmd.envs(&self.env);
         cmd
     }
 
-    /// Performs a shallow clone of a repository
-    pub fn clone_shallow(&self, url: &str, dest: &Path) -> Result<(), GitError> {
-        let output = self
-            .command()
-            .args(["clone", "--depth", "1", url])
-            .arg(dest)
-            .output()
-            .map_err(GitError::IoError)?;
+    /// Returns the version of git, e.g. `2.43.0`
+    pub fn version(&self) -> Result<String, GitError> {
+        let output = self.run(&["--version"])?;
 
-        if output.status.success() {
-            Ok(())
-        } else {
-            Err(GitError::CommandFailed {
-                command: format!("clone {}", url),
-                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
-            })
+        Ok(output
+            .strip_prefix("git version ")
+            .unwrap_or(&output)
+            .to_string())
+    }
+
+    /// Performs a shallow clone of a repository. Progress reported by git is passed to
+    /// `on_progress` line by line while cloning.
+    pub fn clone_shallow(
+        &self,
+        url: &str,
+        dest: &Path,
+        on_progress: &mut dyn FnMut(&str),
+    ) -> Result<(), GitError> {
+        let mut cmd = self.command();
+        cmd.args(["clone", "--progress", "--depth", "1", url])
+            .arg(dest);
+
+        self.run_clone(cmd, url, dest, on_progress)
+    }
+
+    /// Performs a shallow clone of only the given branch (or tag) of a repository, so no other
+    /// branches are fetched. Progress reported by git is passed to `on_progress` line by line while
+    /// cloning.
+    pub fn clone_shallow_branch(
+        &self,
+        url: &str,
+        dest: &Path,
+        branch: &str,
+        on_progress: &mut dyn FnMut(&str),
+    ) -> Result<(), GitError> {
+        let mut cmd = self.command();
+        cmd.args(["clone", "--progress", "--depth", "1", "--single-branch"])
+            .args(["--branch", branch, url])
+            .arg(dest);
+
+        self.run_clone(cmd, url, dest, on_progress)
+    }
+
+    /// Performs a full clone of a repository, including its whole history. Progress reported by
+    /// git is passed to `on_progress` line by line while cloning.
+    pub fn clone_full(
+        &self,
+        url: &str,
+        dest: &Path,
+        on_progress: &mut dyn FnMut(&str),
+    ) -> Result<(), GitError> {
+        let mut cmd = self.command();
+        cmd.args(["clone", "--progress", url]).arg(dest);
+
+        self.run_clone(cmd, url, dest, on_progress)
+    }
+
+    /// Runs a clone command, removing the partially cloned repository if it timed out, since git
+    /// is killed before it can clean up after itself
+    fn run_clone(
+        &self,
+        cmd: Command,
+        url: &str,
+        dest: &Path,
+        on_progress: &mut dyn FnMut(&str),
+    ) -> Result<(), GitError> {
+        let command = format!("clone {}", url);
+        let result = run_with_progress(cmd, command, self.clone_timeout, on_progress);
+
+        if let Err(GitError::Timeout { .. }) = result {
+            let _ = std::fs::remove_dir_all(dest);
         }
+
+        result
+    }
+
+    /// Like [`Git::clone_shallow`], but retries up to `attempts` times with exponential backoff
+    /// when the clone fails with a transient error
+    pub fn clone_shallow_with_retries(
+        &self,
+        url: &str,
+        dest: &Path,
+        attempts: u32,
+        on_progress: &mut dyn FnMut(&str),
+    ) -> Result<(), GitError> {
+        with_retries(attempts, || self.clone_shallow(url, dest, on_progress))
+    }
+
+    /// Like [`Git::clone_shallow_branch`], but retries up to `attempts` times with exponential
+    /// backoff when the clone fails with a transient error
+    pub fn clone_shallow_branch_with_retries(
+        &self,
+        url: &str,
+        dest: &Path,
+        branch: &str,
+        attempts: u32,
+        on_progress: &mut dyn FnMut(&str),
+    ) -> Result<(), GitError> {
+        with_retries(attempts, || {
+            self.clone_shallow_branch(url, dest, branch, on_progress)
+        })
+    }
+
+    /// Like [`Git::clone_full`], but retries up to `attempts` times with exponential backoff when
+    /// the clone fails with a transient error
+    pub fn clone_full_with_retries(
+        &self,
+        url: &str,
+        dest: &Path,
+        attempts: u32,
+        on_progress: &mut dyn FnMut(&str),
+    ) -> Result<(), GitError> {
+        with_retries(attempts, || self.clone_full(url, dest, on_progress))
+    }
+
+    /// Initializes and updates all submodules of the repository, recursively
+    pub fn update_submodules(&self) -> Result<(), GitError> {
+        self.run(&["submodule", "update", "--init", "--recursive"])?;
+        Ok(())
     }
 
     /// Fetches all tags from the remote repository
     pub fn fetch_tags(&self) -> Result<(), GitError> {
         let args = ["fetch", "--all", "--tags"];
-        let output = self
-            .command()
-            .args(args)
-            .output()
-            .map_err(GitError::IoError)?;
+        let mut cmd = self.command();
+        cmd.args(args);
+        log_command(&cmd);
+        let output = cmd.output().map_err(GitError::IoError)?;
 
         if output.status.success() {
             Ok(())
@@ -74,14 +258,43 @@ impl Git {
         }
     }
 
+    /// Fetches a single reference from `origin`, e.g. `refs/tags/v1.0.0` or `refs/heads/main`.
+    /// Branches are stored as remote-tracking branches, so they can be checked out and updated,
+    /// and shallow repositories are kept shallow.
+    pub fn fetch_ref(&self, ref_name: &str) -> Result<(), GitError> {
+        let ref_name = ref_name.trim();
+        let destination = match ref_name.strip_prefix("refs/heads/") {
+            Some(branch) => {
+                // Single-branch clones only track their default branch
+                self.run(&["remote", "set-branches", "--add", "origin", branch])?;
+                format!("refs/remotes/origin/{branch}")
+            }
+            None => ref_name.to_string(),
+        };
+        let refspec = format!("+{ref_name}:{destination}");
+
+        if self.is_shallow()? {
+            self.run(&["fetch", "--depth", "1", "origin", &refspec])?;
+        } else {
+            self.run(&["fetch", "origin", &refspec])?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetches a single commit from `origin`, e.g. one missing from a shallow clone
+    pub fn fetch_commit(&self, commit: &str) -> Result<(), GitError> {
+        self.run(&["fetch", "--depth", "1", "origin", commit.trim()])?;
+        Ok(())
+    }
+
     /// Checks out a specific version (tag, branch, or commit)
     pub fn checkout(&self, version: &str) -> Result<(), GitError> {
         let args = ["checkout", version.trim()];
-        let output = self
-            .command()
-            .args(args)
-            .output()
-            .map_err(GitError::IoError)?;
+        let mut cmd = self.command();
+        cmd.args(args);
+        log_command(&cmd);
+        let output = cmd.output().map_err(GitError::IoError)?;
 
         if output.status.success() {
             Ok(())
@@ -93,22 +306,37 @@ impl Git {
         }
     }
 
-    /// Gets the default branch of the repository
+    /// Moves the checked out branch to `revision`, discarding changes to tracked files
+    pub fn reset_hard(&self, revision: &str) -> Result<(), GitError> {
+        self.run(&["reset", "--hard", revision.trim()])?;
+        Ok(())
+    }
+
+    /// Gets the default branch of the repository. If `origin/HEAD` is not set locally, e.g. in
+    /// some shallow clones, the default branch is asked from the remote instead.
     pub fn get_default_branch(&self) -> Result<String, GitError> {
-        let args = ["rev-parse", "--abbrev-ref", "origin/HEAD"];
-        let output = self
-            .command()
-            .args(args)
-            .output()
-            .map_err(GitError::IoError)?;
+        if let Ok(head) = self.run(&["rev-parse", "--abbrev-ref", "origin/HEAD"])
+            && let Some(branch) = head.strip_prefix("origin/")
+            && !branch.is_empty()
+            && branch != "HEAD"
+        {
+            return Ok(branch.to_string());
+        }
+
+        let output = self.run(&["ls-remote", "--symref", "origin", "HEAD"])?;
+
+        parse_symref_head(&output).ok_or(GitError::NoDefaultBranch)
+    }
+
+    /// Runs a git command and returns its trimmed stdout
+    fn run(&self, args: &[&str]) -> Result<String, GitError> {
+        let mut cmd = self.command();
+        cmd.args(args);
+        log_command(&cmd);
+        let output = cmd.output().map_err(GitError::IoError)?;
 
         if output.status.success() {
-            let branch = String::from_utf8_lossy(&output.stdout)
-                .trim()
-                .strip_prefix("origin/")
-                .unwrap_or("")
-                .to_string();
-            Ok(branch)
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
         } else {
             Err(GitError::CommandFailed {
                 command: args.join(" "),
@@ -116,4 +344,407 @@ impl Git {
             })
         }
     }
+
+    /// Gets the full hash of the currently checked out commit
+    pub fn current_commit(&self) -> Result<String, GitError> {
+        self.run(&["rev-parse", "HEAD"])
+    }
+
+    /// Gets the name of the checked out branch, or `None` if HEAD is detached
+    pub fn current_branch(&self) -> Result<Option<String>, GitError> {
+        let branch = self.run(&["rev-parse", "--abbrev-ref", "HEAD"])?;
+        Ok((branch != "HEAD").then_some(branch))
+    }
+
+    /// Resolves a revision, e.g. a tag or an abbreviated commit hash, to the full hash of the
+    /// commit it points to
+    pub fn resolve_commit(&self, revision: &str) -> Result<String, GitError> {
+        let revision = format!("{}^{{commit}}", revision.trim());
+        self.run(&["rev-parse", "--verify", &revision])
+    }
+
+    /// Gets a human readable name of the checked out version: the tag if one points at it,
+    /// otherwise an abbreviated commit hash
+    pub fn current_ref(&self) -> Result<String, GitError> {
+        self.run(&["describe", "--tags", "--always"])
+    }
+
+    /// Gets the URL of the `origin` remote
+    pub fn remote_url(&self) -> Result<String, GitError> {
+        self.run(&["remote", "get-url", "origin"])
+    }
+
+    /// Points the `origin` remote at another URL, e.g. after the repository moved to another host
+    pub fn set_remote_url(&self, url: &str) -> Result<(), GitError> {
+        self.run(&["remote", "set-url", "origin", url])?;
+        Ok(())
+    }
+
+    /// Checks whether the working tree has uncommitted changes or untracked files
+    pub fn is_dirty(&self) -> Result<bool, GitError> {
+        Ok(!self.run(&["status", "--porcelain"])?.is_empty())
+    }
+
+    /// Checks whether the repository is a shallow clone
+    pub fn is_shallow(&self) -> Result<bool, GitError> {
+        Ok(self.run(&["rev-parse", "--is-shallow-repository"])? == "true")
+    }
+
+    /// Updates the checked out branch to the latest commit of its upstream branch. Shallow
+    /// repositories are kept shallow and moved to the fetched commit, since their history is not
+    /// deep enough for a fast-forward merge.
+    pub fn update(&self) -> Result<(), GitError> {
+        if self.is_shallow()? {
+            self.run(&["fetch", "--depth", "1"])?;
+            self.run(&["reset", "--hard", "@{u}"])?;
+        } else {
+            self.run(&["fetch"])?;
+            self.run(&["merge", "--ff-only", "@{u}"])?;
+        }
+
+        Ok(())
+    }
+
+    /// Compresses the repository and prunes all unreachable objects
+    pub fn gc(&self) -> Result<(), GitError> {
+        self.run(&["gc", "--aggressive", "--prune=now"])?;
+        Ok(())
+    }
+
+    /// Lists tags of the repository, sorted newest first
+    pub fn list_tags(&self) -> Result<Vec<String>, GitError> {
+        let output = self.run(&["tag", "--list"])?;
+        let mut tags: Vec<String> = output.lines().map(str::to_string).collect();
+
+        sort_tags(&mut tags);
+
+        Ok(tags)
+    }
+
+    /// Lists tags of the remote repository at `url` without cloning it, sorted newest first
+    pub fn list_remote_tags(&self, url: &str) -> Result<Vec<String>, GitError> {
+        let output = self.run(&["ls-remote", "--tags", "--refs", url])?;
+        let mut tags = parse_ls_remote_tags(&output);
+
+        sort_tags(&mut tags);
+
+        Ok(tags)
+    }
+
+    /// Gets the tag pointing at the currently checked out commit, if any
+    pub fn current_tag(&self) -> Option<String> {
+        self.run(&["describe", "--tags", "--exact-match"]).ok()
+    }
+}
+
+/// Logs the command line of a git command before running it, shown with `--verbose`
+fn log_command(cmd: &Command) {
+    let args: Vec<_> = cmd.get_args().map(|arg| arg.to_string_lossy()).collect();
+
+    match cmd.get_current_dir() {
+        Some(dir) => log::debug!(
+            "{} {} (in {})",
+            cmd.get_program().to_string_lossy(),
+            args.join(" "),
+            dir.display()
+        ),
+        None => log::debug!("{} {}", cmd.get_program().to_string_lossy(), args.join(" ")),
+    }
+}
+
+/// Runs the command, passing every line it prints to stderr to `on_progress` as soon as it is
+/// printed. Git rewrites progress lines in place using `\r`, so those end a line as well, but only
+/// lines ending with `\n` are kept for the error message. The command is killed if it doesn't
+/// finish within `timeout`.
+fn run_with_progress(
+    mut cmd: Command,
+    command: String,
+    timeout: Option<Duration>,
+    on_progress: &mut dyn FnMut(&str),
+) -> Result<(), GitError> {
+    log_command(&cmd);
+    let mut child = cmd.stdout(Stdio::null()).stderr(Stdio::piped()).spawn()?;
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+
+    // Read in the background, so the timeout is enforced even if git hangs without printing
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = [0; 1024];
+        while let Ok(read @ 1..) = stderr.read(&mut buf) {
+            if tx.send(buf[..read].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut output = String::new();
+    let mut line = Vec::new();
+
+    loop {
+        let chunk = match deadline {
+            Some(deadline) => rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(GitError::Timeout {
+                    command,
+                    timeout: timeout.unwrap_or_default(),
+                });
+            }
+        };
+
+        for &byte in &chunk {
+            if byte != b'\r' && byte != b'\n' {
+                line.push(byte);
+                continue;
+            }
+
+            let text = String::from_utf8_lossy(&line);
+            if !text.trim().is_empty() {
+                on_progress(text.trim());
+            }
+
+            if byte == b'\n' {
+                output.push_str(&text);
+                output.push('\n');
+            }
+
+            line.clear();
+        }
+    }
+
+    output.push_str(&String::from_utf8_lossy(&line));
+
+    if child.wait()?.success() {
+        Ok(())
+    } else {
+        Err(GitError::CommandFailed {
+            command,
+            stderr: output,
+        })
+    }
+}
+
+/// Runs the operation up to `attempts` times, doubling the delay between attempts, for as long as
+/// it fails with a transient error
+fn with_retries(
+    attempts: u32,
+    mut operation: impl FnMut() -> Result<(), GitError>,
+) -> Result<(), GitError> {
+    let mut delay = Duration::from_millis(500);
+    let mut attempt = 1;
+
+    loop {
+        match operation() {
+            Err(error) if error.is_transient() && attempt < attempts => {
+                attempt += 1;
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Parses the branch `HEAD` points to from `git ls-remote --symref <remote> HEAD` output, which
+/// starts with a `ref: refs/heads/<branch>\tHEAD` line
+fn parse_symref_head(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("ref: refs/heads/"))
+        .filter_map(|line| line.strip_suffix("\tHEAD"))
+        .find(|branch| !branch.is_empty())
+        .map(str::to_string)
+}
+
+/// Parses tag names from `git ls-remote --tags` output (`<hash>\trefs/tags/<name>` lines)
+fn parse_ls_remote_tags(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter_map(|(_, reference)| reference.strip_prefix("refs/tags/"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Sorts tags newest first, comparing the numeric components of version-like tags (e.g.
+/// `v1.10.0` is newer than `v1.9.2`)
+fn sort_tags(tags: &mut [String]) {
+    tags.sort_by(|a, b| version_key(b).cmp(&version_key(a)).then_with(|| b.cmp(a)));
+}
+
+/// Sort key of a version-like tag: its numeric release components, whether it is a bare release
+/// and the numeric components of its pre-release suffix. E.g. `v1.2.3-rc1` yields
+/// `([1, 2, 3], false, [1])`, which sorts below the `([1, 2, 3], true, [])` of `v1.2.3`.
+fn version_key(tag: &str) -> (Vec<u64>, bool, Vec<u64>) {
+    let numbers = |part: &str| {
+        part.split(|c: char| !c.is_ascii_digit())
+            .filter_map(|component| component.parse().ok())
+            .collect()
+    };
+
+    let version = tag.trim_start_matches(|c: char| !c.is_ascii_digit());
+    let release_end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    let (release, suffix) = version.split_at(release_end);
+
+    (numbers(release), suffix.is_empty(), numbers(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_util::{TempDir, git, init_repo};
+
+    #[test]
+    fn parse_ls_remote_tags_output() {
+        let output = "1a2b\trefs/tags/v1.0.0\n3c4d\trefs/tags/v1.1.0\n5e6f\trefs/heads/main\n";
+
+        assert_eq!(parse_ls_remote_tags(output), ["v1.0.0", "v1.1.0"]);
+        assert!(parse_ls_remote_tags("").is_empty());
+    }
+
+    #[test]
+    fn sort_tags_newest_first() {
+        let mut tags: Vec<String> = [
+            "v1.2.3-rc1",
+            "v1.9.2",
+            "v1.2.3",
+            "nightly",
+            "v1.10.0",
+            "v1.2.3-rc2",
+        ]
+        .map(str::to_string)
+        .to_vec();
+
+        sort_tags(&mut tags);
+
+        assert_eq!(
+            tags,
+            [
+                "v1.10.0",
+                "v1.9.2",
+                "v1.2.3",
+                "v1.2.3-rc2",
+                "v1.2.3-rc1",
+                "nightly"
+            ]
+        );
+    }
+
+    #[test]
+    fn list_tags_marks_current_tag() {
+        let dir = TempDir::new();
+        init_repo(dir.path());
+        git(dir.path(), &["tag", "v1.0.0"]);
+        git(
+            dir.path(),
+            &["commit", "--quiet", "--allow-empty", "-m", "second"],
+        );
+        git(dir.path(), &["tag", "v1.1.0"]);
+        git(dir.path(), &["checkout", "--quiet", "v1.0.0"]);
+
+        let repo = Git::in_repo(dir.path());
+
+        assert_eq!(repo.list_tags().unwrap(), ["v1.1.0", "v1.0.0"]);
+        assert_eq!(repo.current_tag().as_deref(), Some("v1.0.0"));
+
+        git(dir.path(), &["checkout", "--quiet", "main"]);
+        git(
+            dir.path(),
+            &["commit", "--quiet", "--allow-empty", "-m", "untagged"],
+        );
+
+        assert_eq!(repo.current_tag(), None);
+    }
+
+    #[test]
+    fn current_branch_and_commit_on_branch() {
+        let dir = TempDir::new();
+        init_repo(dir.path());
+        git(dir.path(), &["checkout", "--quiet", "-b", "feature"]);
+
+        let repo = Git::in_repo(dir.path());
+
+        assert_eq!(repo.current_branch().unwrap().as_deref(), Some("feature"));
+        assert_eq!(
+            repo.current_commit().unwrap(),
+            git(dir.path(), &["rev-parse", "HEAD"])
+        );
+    }
+
+    #[test]
+    fn current_branch_on_detached_head() {
+        let dir = TempDir::new();
+        init_repo(dir.path());
+        git(dir.path(), &["checkout", "--quiet", "--detach"]);
+
+        let repo = Git::in_repo(dir.path());
+
+        assert_eq!(repo.current_branch().unwrap(), None);
+        assert_eq!(
+            repo.current_commit().unwrap(),
+            git(dir.path(), &["rev-parse", "HEAD"])
+        );
+    }
+
+    #[test]
+    fn current_branch_and_commit_outside_of_repository() {
+        let dir = TempDir::new();
+        let repo = Git::in_repo(dir.path());
+
+        assert!(matches!(
+            repo.current_commit(),
+            Err(GitError::CommandFailed { .. })
+        ));
+        assert!(matches!(
+            repo.current_branch(),
+            Err(GitError::CommandFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_symref_head_output() {
+        let output = "ref: refs/heads/master\tHEAD\n1a2b3c\tHEAD\n";
+
+        assert_eq!(parse_symref_head(output).as_deref(), Some("master"));
+        assert_eq!(parse_symref_head(""), None);
+        assert_eq!(parse_symref_head("1a2b3c\tHEAD\n"), None);
+    }
+
+    #[test]
+    fn default_branch_without_origin_head() {
+        let dir = TempDir::new();
+        let remote = dir.path().join("remote");
+        init_repo(&remote);
+        git(&remote, &["branch", "--quiet", "-m", "trunk"]);
+        git(dir.path(), &["clone", "--quiet", "remote", "plugin"]);
+
+        let plugin = dir.path().join("plugin");
+        git(&plugin, &["remote", "set-head", "origin", "--delete"]);
+
+        let repo = Git::in_repo(&plugin);
+        assert_eq!(repo.get_default_branch().unwrap(), "trunk");
+
+        // A remote without HEAD, e.g. an empty one, has no default branch
+        let empty = dir.path().join("empty");
+        fs::create_dir_all(&empty).unwrap();
+        git(&empty, &["init", "--quiet", "--bare"]);
+        git(&plugin, &["remote", "set-url", "origin", "../empty"]);
+
+        assert!(matches!(
+            repo.get_default_branch(),
+            Err(GitError::NoDefaultBranch)
+        ));
+    }
 }
@@ -1,4 +1,14 @@
 pub mod config;
+pub mod disk;
 pub mod error;
+pub mod export;
 pub mod git;
+pub mod lock;
+pub mod orphan;
 pub mod plugin;
+pub mod report;
+pub mod state;
+pub mod verify;
+
+#[cfg(test)]
+pub(crate) mod test_util;
@@ -0,0 +1,143 @@
+//! Lockfile pinning every installed plugin to an exact commit
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::atomic_write;
+use crate::error::PluxError;
+use crate::plugin::PluginSpec;
+
+/// Models the `plux.lock` file, stored next to the plugin spec file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LockFile {
+    #[serde(default)]
+    pub plugins: BTreeMap<String, LockedPlugin>,
+}
+
+/// Exact commit a plugin was installed at, along with the specification it was resolved from.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LockedPlugin {
+    pub url: String,
+    /// Version from the specification (e.g. `tag 'v1.0.0'`), if any.
+    pub version: Option<String>,
+    /// Full hash of the installed commit.
+    pub commit: String,
+}
+
+impl LockedPlugin {
+    pub fn new(spec: &PluginSpec, commit: String) -> Self {
+        Self {
+            url: spec.url().to_string(),
+            version: spec.version().map(ToString::to_string),
+            commit,
+        }
+    }
+
+    /// Whether this entry was resolved from the given specification, i.e. the specification did
+    /// not change since the plugin was locked.
+    pub fn matches(&self, spec: &PluginSpec) -> bool {
+        self.url == spec.url() && self.version == spec.version().map(ToString::to_string)
+    }
+}
+
+impl LockFile {
+    /// Returns the path of the lockfile belonging to the given spec file.
+    pub fn path(spec_path: &Path) -> PathBuf {
+        spec_path.with_extension("lock")
+    }
+
+    /// Loads the lockfile, returning an empty one if it doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, PluxError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents).map_err(|e| PluxError::ConfigParse {
+                path: path.to_owned(),
+                format: "TOML",
+                source: e.into(),
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(PluxError::ConfigRead {
+                path: path.to_owned(),
+                source: e,
+            }),
+        }
+    }
+
+    /// Writes the lockfile to the given path.
+    pub fn save(&self, path: &Path) -> Result<(), PluxError> {
+        let contents = toml::to_string(self)?;
+
+        atomic_write(path, contents).map_err(|e| PluxError::ConfigWrite {
+            path: path.to_owned(),
+            source: e,
+        })
+    }
+
+    /// Returns the locked commit of the plugin, unless its specification changed since it was
+    /// locked.
+    pub fn locked_commit(&self, name: &str, spec: &PluginSpec) -> Option<&str> {
+        self.plugins
+            .get(name)
+            .filter(|locked| locked.matches(spec))
+            .map(|locked| locked.commit.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn spec(version: &str) -> PluginSpec {
+        let file: crate::plugin::PluginSpecFile = toml::from_str(&format!(
+            "[plugins]\nyank = {{ url = \"https://github.com/tmux-plugins/tmux-yank\", {version} }}"
+        ))
+        .unwrap();
+        file.plugins["yank"].clone()
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = TempDir::new();
+        let path = LockFile::path(&dir.path().join("plux.toml"));
+
+        let mut lock = LockFile::default();
+        lock.plugins.insert(
+            "yank".to_string(),
+            LockedPlugin::new(&spec(r#"tag = "v2.3.0""#), "1a2b3c".to_string()),
+        );
+        lock.save(&path).unwrap();
+
+        assert_eq!(path, dir.path().join("plux.lock"));
+        assert_eq!(LockFile::load(&path).unwrap(), lock);
+    }
+
+    #[test]
+    fn load_missing_lockfile() {
+        let dir = TempDir::new();
+
+        assert_eq!(
+            LockFile::load(&dir.path().join("plux.lock")).unwrap(),
+            LockFile::default()
+        );
+    }
+
+    #[test]
+    fn locked_commit_ignores_changed_spec() {
+        let mut lock = LockFile::default();
+        lock.plugins.insert(
+            "yank".to_string(),
+            LockedPlugin::new(&spec(r#"tag = "v2.3.0""#), "1a2b3c".to_string()),
+        );
+
+        assert_eq!(
+            lock.locked_commit("yank", &spec(r#"tag = "v2.3.0""#)),
+            Some("1a2b3c")
+        );
+        assert_eq!(lock.locked_commit("yank", &spec(r#"tag = "v3.0.0""#)), None);
+        assert_eq!(
+            lock.locked_commit("copycat", &spec(r#"tag = "v2.3.0""#)),
+            None
+        );
+    }
+}
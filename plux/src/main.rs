@@ -1,10 +1,24 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::RecvTimeoutError,
+    time::{Duration, Instant},
+};
 
 use clap::Parser;
 use murus::Tmux;
-use plux::config::Config;
+use plux::config::{Config, ConfigOverrides};
 use plux::error::PluxError;
-use plux::plugin::{InstallError, PluginSpec, PluginSpecFile};
+use plux::export::ExportFormat;
+use plux::lock::{LockFile, LockedPlugin};
+use plux::plugin::{
+    InstallError, PluginSpec, SourceMode, Version, ensure_clean, is_runnable_tmux_file,
+    normalize_url,
+};
+use plux::report::{CloneStats, InstallReport};
+use plux::state::RunState;
+use plux::verify::{Verification, verify_plugin};
 
 const HELP_TEMPLATE: &str = r#"
 {before-help}{name} {version}
@@ -17,8 +31,10 @@ const HELP_TEMPLATE: &str = r#"
 
 const AFTER_HELP: &str = r#"
 CONFIGURATION:
-  Plugin spec file:   ~/.config/tmux/plux.toml  (customize with @plux_toml_path)
-  Plugins directory:  ~/.config/tmux/plux/      (customize with @plux_plugins_path)
+  Plugin spec file:   ~/.config/tmux/plux.toml  (customize with @plux_toml_path or --config)
+  Plugins directory:  ~/.config/tmux/plux/      (customize with @plux_plugins_path or --plugins-dir)
+
+  Command line options take precedence over tmux options, which take precedence over the defaults.
 
 PLUGIN SPECIFICATION:
   The plux.toml file contains a [plugins] table mapping plugin names to URLs:
@@ -29,10 +45,27 @@ PLUGIN SPECIFICATION:
 PLUGIN EXECUTION:
   Plux maintains backward compatibility with TPM plugins using two execution modes:
     1. If "plux.tmux" exists in the plugin root → sourced via tmux source-file
-    2. Otherwise → all *.tmux files executed via tmux run-shell
+    2. Otherwise → executable *.tmux files in the plugin root executed via tmux run-shell
+  A plugin with an "entry" file in the spec has only that file sourced via tmux source-file.
+  The "source_mode" of a plugin (or @plux_default_source_mode) selects "auto" (the above),
+  "source_file" or "run_shell" to always load its *.tmux files with that command.
+  Plugins are sourced one at a time, by ascending "priority" (default 0), then by name.
+
+EXIT CODES:
+  0  All plugins were installed and loaded
+  1  Plux could not run, e.g. outside of tmux or with an invalid configuration
+  2  Some plugins failed to install or load, or don't match the spec (plux verify)
 
 "#;
 
+/// Minimum delay between two clone progress messages of the same plugin shown in tmux
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Delay between two frames of the install spinner shown in the terminal
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
 const LOGO: &str = r#"
 __________.____     ____ _______  ___
 \______   \    |   |    |   \   \/  /
@@ -46,22 +79,180 @@ __________.____     ____ _______  ___
 #[command(version, author, about, long_about = None)]
 #[command(help_template = HELP_TEMPLATE)]
 #[command(after_help = AFTER_HELP)]
-struct CliArgs;
+struct CliArgs {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Update installed plugins that follow a branch to its latest commit
+    #[arg(long)]
+    update: bool,
+
+    /// Report what would be done without changing any plugins
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Reinstall the given plugins from scratch, or all plugins if none are given
+    #[arg(long, num_args = 0.., value_name = "PLUGIN")]
+    force: Option<Vec<String>>,
+
+    /// Print the outcome of the run as a single JSON object instead of human readable output
+    #[arg(long, conflicts_with_all = ["dry_run", "quiet", "verbose"])]
+    json: bool,
+
+    /// Only print errors, without the banner, progress and status messages
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Also print the git commands being run and every file being sourced
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Plugin spec file to use, takes precedence over @plux_toml_path
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Plugins directory to use, takes precedence over @plux_plugins_path
+    #[arg(long, global = true, value_name = "PATH")]
+    plugins_dir: Option<PathBuf>,
+}
+
+impl CliArgs {
+    /// Returns the configuration paths given on the command line. Relative paths are resolved
+    /// against the current directory, since plux doesn't necessarily run from it.
+    fn config_overrides(&self) -> ConfigOverrides {
+        let absolute = |path: &PathBuf| std::path::absolute(path).unwrap_or_else(|_| path.clone());
+
+        ConfigOverrides {
+            spec_path: self.config.as_ref().map(absolute),
+            plugins_path: self.plugins_dir.as_ref().map(absolute),
+        }
+    }
+}
+
+/// Options controlling the default plux run
+struct RunOptions {
+    update: bool,
+    dry_run: bool,
+    /// Plugins to reinstall, all of them if empty
+    force: Option<Vec<String>>,
+    /// Print a JSON report instead of logging progress and results
+    json: bool,
+    overrides: ConfigOverrides,
+}
+
+impl RunOptions {
+    /// Checks whether the plugin should be reinstalled even if it is already installed
+    fn is_forced(&self, plugin_name: &str) -> bool {
+        self.force
+            .as_ref()
+            .is_some_and(|names| names.is_empty() || names.iter().any(|name| name == plugin_name))
+    }
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Export the plugin specification so it can be installed without plux
+    Export {
+        /// Format of the exported specification
+        #[arg(long, value_enum, default_value_t = ExportFormat::Sh)]
+        format: ExportFormat,
+    },
+    /// Uninstall a plugin and remove it from the plugin spec file
+    Uninstall {
+        /// Name of the plugin, as specified in the plugin spec file
+        name: String,
+    },
+    /// List plugins with their installed versions, flagging orphaned plugins
+    List {
+        /// Show the on-disk size of every plugin, largest first
+        #[arg(long)]
+        size: bool,
+    },
+    /// List available versions (tags) of a plugin
+    Versions {
+        /// Name of the plugin, as specified in the plugin spec file
+        name: String,
+    },
+    /// Print a compact summary of the last run, suitable for the tmux status bar
+    Status,
+    /// Check that installed plugins are checked out at the versions requested by the spec
+    Verify {
+        /// Check out the requested version of plugins that don't match the spec
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Start tmux from a plain shell, creating a dedicated session unless it exists and attaching
+    /// to it
+    Init {
+        /// Name of the session to create or attach to
+        #[arg(long, default_value = "plux")]
+        session: String,
+    },
+    /// Check that tmux, git and the plux configuration are set up correctly
+    Doctor,
+    /// Compact the git repositories of installed plugins to reclaim disk space
+    Clean {
+        /// Also remove the git history of plugins pinned to a commit, since they never update
+        #[arg(long)]
+        strip_history: bool,
+    },
+}
 
 fn main() {
     // Parse CLI args first - this will handle --help and --version and exit early
-    let _ = CliArgs::parse();
+    let args = CliArgs::parse();
+
+    let level = if args.json {
+        log::LevelFilter::Off
+    } else if args.quiet {
+        log::LevelFilter::Error
+    } else if args.verbose {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    };
+    log::set_logger(&Logger).expect("logger is only set once");
+    log::set_max_level(level);
+
+    let overrides = args.config_overrides();
+
+    if let Some(command) = args.command {
+        if let Err(error) = run_command(command, &overrides) {
+            eprintln!("Error: {error}");
+            std::process::exit(error.exit_code());
+        }
+
+        return;
+    }
 
     // Only show banner when actually running the plugin manager
     if let Ok(tmux) = Tmux::try_new() {
         let banner = format!(" plux v{} - tmux plugin manager", env!("CARGO_PKG_VERSION"));
-        println!("{}\n{}", LOGO, banner);
-        println!("——————————————————————————————————————");
+        log::info!("{}\n{}", LOGO, banner);
+        log::info!("——————————————————————————————————————");
 
-        let _ = tmux.display_message_with_duration(&banner, 500);
+        notify(&tmux, &banner, 500);
     }
 
-    if let Err(error) = run() {
+    let options = RunOptions {
+        update: args.update,
+        dry_run: args.dry_run,
+        force: args.force,
+        json: args.json,
+        overrides,
+    };
+
+    if let Err(error) = run(&options) {
+        if options.json {
+            // NOTE: failed plugins are already part of the printed report
+            if !matches!(error, PluxError::PluginsFailed(_)) {
+                let report = serde_json::json!({ "success": false, "error": error.to_string() });
+                println!("{report}");
+            }
+
+            std::process::exit(error.exit_code());
+        }
+
         println!("Error: {error}");
 
         // Provide helpful context based on error type
@@ -70,10 +261,10 @@ fn main() {
                 println!("\nPlux must be run inside a tmux session.");
                 println!("Start tmux first with: tmux");
             }
-            PluxError::ConfigParse { path, .. } => {
+            PluxError::ConfigParse { path, format, .. } => {
                 println!("\nTroubleshooting:");
-                println!("  1. Check TOML syntax in {}", path.display());
-                println!("  2. Ensure [plugins] section exists");
+                println!("  1. Check {format} syntax in {}", path.display());
+                println!("  2. Ensure the plugins table is a map of plugin names to URLs");
                 println!(
                     "  3. Or delete the file and run plux again to regenerate the default config"
                 );
@@ -81,40 +272,481 @@ fn main() {
             _ => {}
         }
 
-        std::process::exit(1);
+        println!("\nRun 'plux doctor' to check your setup for common problems.");
+
+        std::process::exit(error.exit_code());
+    }
+}
+
+fn run_command(command: Command, overrides: &ConfigOverrides) -> Result<(), PluxError> {
+    // Runs outside of tmux, so it can't load the config from tmux options
+    if let Command::Init { session } = &command {
+        return init_session(session);
+    }
+
+    // Diagnoses why plux might not work, so it must not require a working setup itself
+    if let Command::Doctor = command {
+        return doctor(overrides);
+    }
+
+    let tmux = Tmux::try_new().map_err(|_| PluxError::NotInTmux)?;
+    let mut config = Config::load_with_overrides(&tmux, overrides)?;
+
+    match command {
+        Command::Export { format } => {
+            print!("{}", plux::export::export(&config, format));
+        }
+        Command::Uninstall { name } => uninstall_plugin(&mut config, &name)?,
+        Command::List { size } => list_plugins(&config, size),
+        Command::Versions { name } => print_versions(&config, &name)?,
+        Command::Status => match RunState::load(&config.plugins_path) {
+            Some(state) => println!("{}", state.render_status()),
+            None => println!("?"),
+        },
+        Command::Verify { fix } => verify_plugins(&config, fix)?,
+        Command::Clean { strip_history } => clean_plugins(&config, strip_history),
+        Command::Init { .. } | Command::Doctor => {
+            unreachable!("handled before loading the config")
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates the session unless it exists and attaches to it. Plugins are installed and loaded by
+/// the `run-shell plux` line in tmux.conf once the tmux server starts, as in every other session.
+fn init_session(name: &str) -> Result<(), PluxError> {
+    if Tmux::is_inside() {
+        log::info!("Already running inside tmux, nothing to do");
+        return Ok(());
+    }
+
+    let tmux = Tmux::new_unchecked();
+
+    if !tmux.has_session(name)? {
+        tmux.new_session(name, None)?;
+        log::info!("Created session '{name}'");
+    }
+
+    tmux.attach_session(name)?;
+
+    Ok(())
+}
+
+/// Checks the environment plux runs in, printing one line per check. Fails if any check that
+/// keeps plux from working fails, other problems are only reported as warnings.
+fn doctor(overrides: &ConfigOverrides) -> Result<(), PluxError> {
+    let mut failed = 0;
+    let mut check = |critical: bool, result: Result<String, String>| match result {
+        Ok(message) => println!("  [OK] {message}"),
+        Err(message) if critical => {
+            failed += 1;
+            println!("  [FAIL] {message}");
+        }
+        Err(message) => println!("  [WARN] {message}"),
+    };
+
+    check(
+        true,
+        std::env::var("HOME")
+            .map(|home| format!("$HOME is set to {home}"))
+            .map_err(|_| "$HOME is not set".to_string()),
+    );
+
+    let tmux = Tmux::new_unchecked();
+    check(
+        true,
+        tmux.version()
+            .map(|(major, minor)| format!("tmux {major}.{minor} found"))
+            .map_err(|error| format!("tmux could not be run: {error}")),
+    );
+    check(
+        false,
+        Tmux::is_inside()
+            .then(|| "Running inside tmux".to_string())
+            .ok_or_else(|| "Not running inside tmux, start tmux first or run 'plux init'".into()),
+    );
+
+    let config = match Config::load_with_overrides(&tmux, overrides) {
+        Ok(config) => {
+            check(
+                true,
+                Ok(format!(
+                    "Plugin spec file {} is valid",
+                    config.spec_path.display()
+                )),
+            );
+            config
+        }
+        Err(error) => {
+            check(
+                true,
+                Err(format!("Configuration could not be loaded: {error}")),
+            );
+            return Err(PluxError::ChecksFailed(failed));
+        }
+    };
+
+    check(
+        true,
+        config
+            .git()
+            .version()
+            .map(|version| format!("git {version} found"))
+            .map_err(|error| format!("git could not be run: {error}")),
+    );
+
+    let probe = config.plugins_path.join(".plux-doctor");
+    check(
+        true,
+        fs::write(&probe, "")
+            .and_then(|_| fs::remove_file(&probe))
+            .map(|_| {
+                format!(
+                    "Plugins directory {} is writable",
+                    config.plugins_path.display()
+                )
+            })
+            .map_err(|error| {
+                format!(
+                    "Plugins directory {} is not writable: {error}",
+                    config.plugins_path.display()
+                )
+            }),
+    );
+
+    if failed > 0 {
+        return Err(PluxError::ChecksFailed(failed));
+    }
+
+    Ok(())
+}
+
+fn uninstall_plugin(config: &mut Config, name: &str) -> Result<(), PluxError> {
+    let spec = config
+        .spec
+        .plugins
+        .get(name)
+        .ok_or_else(|| PluxError::UnknownPlugin(name.to_string()))?;
+
+    let plugin_dir = config.plugin_dir(name, spec);
+
+    // Drop the spec entry first, so a failed write leaves the plugin fully installed
+    config.remove_plugin(name)?;
+
+    if plugin_dir.is_dir() {
+        fs::remove_dir_all(&plugin_dir).map_err(|e| PluxError::PluginRemoval {
+            path: plugin_dir.clone(),
+            source: e,
+        })?;
+    }
+
+    println!("  Uninstalled plugin: {name}");
+
+    Ok(())
+}
+
+fn list_plugins(config: &Config, show_size: bool) {
+    let size_of = |dir: &Path| (show_size && dir.is_dir()).then(|| plux::disk::dir_size(dir));
+
+    // Rows of name, URL, version and size
+    let mut rows: Vec<_> = config
+        .spec
+        .plugins
+        .iter()
+        .map(|(name, spec)| {
+            let plugin_dir = config.plugin_dir(name, spec);
+            let version = if plugin_dir.is_dir() {
+                config
+                    .git()
+                    .for_repo(&plugin_dir)
+                    .current_ref()
+                    .unwrap_or_else(|_| "unknown".to_string())
+            } else {
+                "not installed".to_string()
+            };
+
+            (
+                name.clone(),
+                spec.url().to_string(),
+                version,
+                size_of(&plugin_dir),
+            )
+        })
+        .collect();
+
+    rows.extend(
+        plux::orphan::find_orphans(config)
+            .into_iter()
+            .map(|orphan| {
+                let size = size_of(&config.plugins_path.join(&orphan));
+                let url = config
+                    .git()
+                    .for_repo(config.plugins_path.join(&orphan))
+                    .remote_url()
+                    .unwrap_or_default();
+
+                (
+                    orphan.display().to_string(),
+                    url,
+                    "orphaned".to_string(),
+                    size,
+                )
+            }),
+    );
+
+    rows.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+    if show_size {
+        plux::disk::sort_largest_first(&mut rows, |(.., size)| *size);
+    }
+
+    let name_width = rows.iter().map(|(name, ..)| name.len()).max().unwrap_or(0);
+    let url_width = rows.iter().map(|(_, url, ..)| url.len()).max().unwrap_or(0);
+
+    for (name, url, version, size) in &rows {
+        let line = format!("  {name:name_width$}  {url:url_width$}  {version}");
+
+        match size {
+            Some(size) => println!("{line} ({})", plux::disk::format_size(*size)),
+            None => println!("{line}"),
+        }
+    }
+
+    if show_size {
+        let total = rows.iter().filter_map(|(.., size)| *size).sum();
+        println!("Total: {}", plux::disk::format_size(total));
+    }
+}
+
+fn verify_plugins(config: &Config, fix: bool) -> Result<(), PluxError> {
+    let lock = LockFile::load(&LockFile::path(&config.spec_path)).unwrap_or_default();
+    let git = config.git();
+
+    let mut plugins: Vec<_> = config.spec.plugins.iter().collect();
+    plugins.sort_by_key(|(name, _)| name.as_str());
+
+    let mut failed = 0;
+
+    for (name, spec) in plugins {
+        let plugin_dir = config.plugin_dir(name, spec);
+        let locked_commit = lock.locked_commit(name, spec);
+        let verification = verify_plugin(&git, &plugin_dir, spec, locked_commit);
+
+        if fix && verification.is_mismatch() {
+            let result = ensure_clean(&git, &plugin_dir).and_then(|_| match locked_commit {
+                Some(commit) => spec.choose_locked_version(&git, &plugin_dir, commit),
+                None => spec.choose_version(&git, &plugin_dir),
+            });
+
+            match result {
+                Ok(version) => println!("  [FIXED] {name} (checked out {version})"),
+                Err(error) => {
+                    failed += 1;
+                    eprintln!("  [ERROR] {name} - Failed to fix: {error}");
+                }
+            }
+            continue;
+        }
+
+        match verification {
+            Verification::Matches => println!("  [OK] {name}"),
+            Verification::NotInstalled => println!("  [SKIP] {name} (not installed)"),
+            Verification::Drifted { expected, actual } => {
+                failed += 1;
+                println!("  [DRIFT] {name} (expected {expected}, found {actual})");
+            }
+            Verification::Detached { expected } => {
+                failed += 1;
+                println!("  [DETACHED] {name} (expected {expected})");
+            }
+            Verification::Dirty => {
+                failed += 1;
+                println!("  [DIRTY] {name} (uncommitted changes)");
+            }
+            Verification::Error(error) => {
+                failed += 1;
+                eprintln!("  [ERROR] {name} - {error}");
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(PluxError::VerificationFailed(failed));
+    }
+
+    Ok(())
+}
+
+fn clean_plugins(config: &Config, strip_history: bool) {
+    let mut plugins: Vec<_> = config.spec.plugins.iter().collect();
+    plugins.sort_by_key(|(name, _)| name.as_str());
+
+    let mut total_reclaimed = 0;
+
+    for (name, spec) in plugins {
+        let plugin_dir = config.plugin_dir(name, spec);
+        let git_dir = plugin_dir.join(".git");
+        if !git_dir.exists() {
+            continue;
+        }
+
+        let size_before = plux::disk::dir_size(&plugin_dir);
+
+        // Plugins following a branch or tag keep their history so they can still be updated
+        let result = if strip_history && matches!(spec.version(), Some(Version::Commit(_))) {
+            fs::remove_dir_all(&git_dir).map_err(|e| e.to_string())
+        } else {
+            config
+                .git()
+                .for_repo(&plugin_dir)
+                .gc()
+                .map_err(|e| e.to_string())
+        };
+
+        if let Err(error) = result {
+            eprintln!("  [ERROR] {name} - Failed to clean: {error}");
+            continue;
+        }
+
+        let reclaimed = size_before.saturating_sub(plux::disk::dir_size(&plugin_dir));
+        total_reclaimed += reclaimed;
+
+        println!(
+            "  [OK] {name} ({} reclaimed)",
+            plux::disk::format_size(reclaimed)
+        );
     }
+
+    println!();
+    println!(
+        "  Reclaimed {} in total",
+        plux::disk::format_size(total_reclaimed)
+    );
 }
 
-fn run() -> Result<(), PluxError> {
+fn print_versions(config: &Config, name: &str) -> Result<(), PluxError> {
+    let spec = config
+        .spec
+        .plugins
+        .get(name)
+        .ok_or_else(|| PluxError::UnknownPlugin(name.to_string()))?;
+
+    let plugin_dir = config.plugin_dir(name, spec);
+
+    // Installed plugins already have the tags locally, others are queried without cloning
+    let (tags, current) = if plugin_dir.is_dir() {
+        let git = config.git().for_repo(&plugin_dir);
+        (git.list_tags()?, git.current_tag())
+    } else {
+        (config.git().list_remote_tags(spec.url())?, None)
+    };
+
+    if tags.is_empty() {
+        println!("No versions available for {name}");
+        return Ok(());
+    }
+
+    println!("Available versions of {name}:");
+    for tag in tags {
+        if current.as_ref() == Some(&tag) {
+            println!("  {tag} (current)");
+        } else {
+            println!("  {tag}");
+        }
+    }
+
+    Ok(())
+}
+
+fn run(options: &RunOptions) -> Result<(), PluxError> {
     let tmux = Tmux::try_new().map_err(|_| PluxError::NotInTmux)?;
-    let config = Config::load(&tmux)?;
+    let config = Config::load_with_overrides(&tmux, &options.overrides)?;
+
+    if let Some(unknown) = options
+        .force
+        .iter()
+        .flatten()
+        .find(|name| !config.spec.plugins.contains_key(*name))
+    {
+        return Err(PluxError::UnknownPlugin(unknown.clone()));
+    }
 
     // Show progress via display-message for real-time feedback in tmux
-    let _ = tmux.display_message_with_duration(" PLUX | Checking for orphaned plugins...", 1000);
-    remove_orphaned_plugins(&config.plugins_path, &config.spec);
+    notify(&tmux, " PLUX | Checking for orphaned plugins...", 1000);
+    remove_orphaned_plugins(&config, options.dry_run);
+
+    notify(&tmux, " PLUX | Installing plugins...", 20_000);
+    let lock_path = LockFile::path(&config.spec_path);
+    let lock = LockFile::load(&lock_path).unwrap_or_else(|error| {
+        log::warn!("Ignoring lockfile: {error}");
+        LockFile::default()
+    });
+
+    let install_report = install_plugins(&config, &tmux, &lock, options);
+    print_report(&install_report);
+
+    if options.update {
+        notify(&tmux, " PLUX | Updating plugins...", 20_000);
+        update_plugins(&config, options);
+    }
 
-    let _ = tmux.display_message_with_duration(" PLUX | Installing plugins...", 20_000);
-    install_plugins(&config.plugins_path, config.spec.clone());
+    let resolved = resolve_lock(&config);
 
-    let _ = tmux.display_message_with_duration(" PLUX | Sourcing plugins...", 1000);
-    source_plugins(&config.plugins_path, &config.spec, &tmux);
+    if !options.dry_run
+        && let Err(error) = resolved.save(&lock_path)
+    {
+        log::warn!("Could not write lockfile: {error}");
+    }
+
+    notify(&tmux, " PLUX | Sourcing plugins...", 1000);
+    let source_report = source_plugins(&config, &tmux, options.dry_run);
+
+    if options.dry_run {
+        println!();
+        println!("Dry run completed, no changes were made.");
+        return Ok(());
+    }
+
+    print_report(&source_report);
+
+    if let Err(error) = install_report
+        .run_state(&source_report)
+        .save(&config.plugins_path)
+    {
+        log::warn!("Could not persist run state: {error}");
+    }
+
+    if options.json {
+        print_json_report(&install_report, &source_report, &resolved);
+    }
+
+    // Every plugin was attempted, report failures through the exit code
+    if install_report.has_failures() || source_report.has_failures() {
+        let failed = install_report.failed.len() + source_report.failed.len();
+        show_message(
+            &tmux,
+            &format!("Plux completed! {failed} plugin(s) failed to install or load"),
+            5000,
+        );
+        return Err(PluxError::PluginsFailed(failed));
+    }
 
     // Success message - show immediately via display-message
-    let plugin_count = config.spec.plugins.len();
+    let plugin_count = source_report.installed.len();
     let success_msg = if plugin_count > 0 {
         format!("Plux completed! {} plugin(s) loaded", plugin_count)
     } else {
         "Plux completed! No plugins configured yet".to_string()
     };
-    let _ = tmux.display_message_with_duration(&success_msg, 1000);
+    notify(&tmux, &success_msg, 1000);
 
     // Also log detailed info to stdout
-    println!();
-    println!("Plux completed successfully!");
+    log::info!("");
+    log::info!("Plux completed successfully!");
     if plugin_count > 0 {
-        println!("  {} plugin(s) loaded and sourced", plugin_count);
+        log::info!("  {} plugin(s) loaded and sourced", plugin_count);
     } else {
-        println!(
+        log::info!(
             "  No plugins configured. Add plugins to {} to get started.",
             config.spec_path.display()
         );
@@ -123,159 +755,695 @@ fn run() -> Result<(), PluxError> {
     Ok(())
 }
 
-fn remove_orphaned_plugins(plugins_path: &Path, plugin_spec: &PluginSpecFile) {
-    // If plugins directory doesn't exist, nothing to clean up
-    if !plugins_path.exists() {
-        return;
+/// Shows a status message in tmux, unless running with `--quiet`.
+fn notify(tmux: &Tmux, message: &str, duration_ms: u32) {
+    if log::log_enabled!(log::Level::Info) {
+        show_message(tmux, message, duration_ms);
     }
+}
 
-    let Ok(entries) = fs::read_dir(plugins_path) else {
-        eprintln!(
-            "Could not read plugins directory at {}",
-            plugins_path.display()
-        );
+/// Shows a message in tmux, skipping it when there is no attached client to display it on.
+fn show_message(tmux: &Tmux, message: &str, duration_ms: u32) {
+    if tmux.has_attached_client().unwrap_or(false) {
+        let _ = tmux.display_message_with_duration(message, duration_ms);
+    }
+}
+
+/// Prints log messages to the terminal: informational messages go to stdout, warnings, errors and
+/// debug output to stderr.
+struct Logger;
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        match record.level() {
+            log::Level::Info => println!("{}", record.args()),
+            _ => eprintln!("{}", record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Progress of installing plugins, shown as a spinner in the terminal and as `N/M installed` in
+/// tmux, so long running clones don't look like plux hung.
+struct InstallProgress<'a> {
+    tmux: &'a Tmux,
+    total: usize,
+    done: usize,
+    frame: usize,
+    /// Whether to draw the spinner, only done when stderr is a terminal
+    spinner: bool,
+    last_notified: Option<Instant>,
+}
+
+impl<'a> InstallProgress<'a> {
+    fn new(tmux: &'a Tmux, total: usize) -> Self {
+        Self {
+            tmux,
+            total,
+            done: 0,
+            frame: 0,
+            // Debug output is printed to stderr as well, and would be mixed up with the spinner
+            spinner: std::io::stderr().is_terminal() && !log::log_enabled!(log::Level::Debug),
+            last_notified: None,
+        }
+    }
+
+    /// Advances the spinner to the next frame.
+    fn tick(&mut self) {
+        self.frame = (self.frame + 1) % SPINNER_FRAMES.len();
+        self.draw();
+    }
+
+    /// Records that another plugin is done installing.
+    fn advance(&mut self) {
+        self.done += 1;
+        self.draw();
+
+        let finished = self.done == self.total;
+        if finished
+            || self
+                .last_notified
+                .is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL)
+        {
+            self.last_notified = Some(Instant::now());
+            notify(
+                self.tmux,
+                &format!(" PLUX | {}/{} installed", self.done, self.total),
+                if finished { 1000 } else { 20_000 },
+            );
+        }
+    }
+
+    fn draw(&self) {
+        if self.spinner {
+            let mut stderr = std::io::stderr();
+            let _ = write!(
+                stderr,
+                "\r{} Installing plugins {}/{}",
+                SPINNER_FRAMES[self.frame], self.done, self.total
+            );
+            let _ = stderr.flush();
+        }
+    }
+
+    /// Clears the spinner so the report can be printed in its place.
+    fn finish(&self) {
+        if self.spinner {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+fn remove_orphaned_plugins(config: &Config, dry_run: bool) {
+    let orphans = plux::orphan::find_orphans(config);
+    if orphans.is_empty() {
         return;
-    };
+    }
+
+    let mut renamed = plux::orphan::find_renamed(config, &orphans);
+
+    for orphan in orphans {
+        let plugin_path = config.plugins_path.join(&orphan);
+
+        // The plugin was renamed in the spec file - move it instead of cloning it again
+        if let Some(destination) = renamed.remove(&orphan) {
+            if dry_run {
+                println!(
+                    "  [DRY RUN] Would move plugin: {} -> {}",
+                    orphan.display(),
+                    destination.display()
+                );
+                continue;
+            }
+
+            match move_plugin(&plugin_path, &destination) {
+                Ok(_) => {
+                    log::info!(
+                        "  Moved plugin: {} -> {}",
+                        orphan.display(),
+                        destination.display()
+                    );
+                    continue;
+                }
+                Err(error) => {
+                    log::error!("  Failed to move plugin '{}': {}", orphan.display(), error);
+                }
+            }
+        }
 
-    for entry in entries.flatten() {
-        let Ok(file_type) = entry.file_type() else {
+        if !plux::orphan::is_plugin_dir(&config.git(), &plugin_path) {
+            log::warn!(
+                "  Skipping {}: not a plugin cloned by plux",
+                orphan.display()
+            );
             continue;
-        };
+        }
 
-        // Only consider directories
-        if !file_type.is_dir() {
+        if !config.auto_remove_orphans {
+            log::info!(
+                "  Orphaned plugin: {} (set @plux_auto_remove_orphans to 'on' to remove it)",
+                orphan.display()
+            );
             continue;
         }
 
-        let dir_name_os = entry.file_name();
-        let Some(dir_name) = dir_name_os.to_str() else {
+        // This is an orphaned plugin - remove it
+        if dry_run {
+            println!(
+                "  [DRY RUN] Would remove orphaned plugin: {}",
+                orphan.display()
+            );
             continue;
+        }
+
+        match fs::remove_dir_all(&plugin_path) {
+            Ok(_) => {
+                log::info!("  Removed orphaned plugin: {}", orphan.display());
+            }
+            Err(error) => {
+                log::error!(
+                    "  Failed to remove orphaned plugin '{}': {}",
+                    orphan.display(),
+                    error
+                );
+            }
+        }
+    }
+}
+
+/// Moves an installed plugin to a new directory, creating missing parent directories.
+fn move_plugin(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::rename(from, to)
+}
+
+/// Sources the installed plugins one after another, in the order given by
+/// [`plux::plugin::PluginSpecFile::sourcing_order`], so order-sensitive setups load reproducibly.
+fn source_plugins(config: &Config, tmux: &Tmux, dry_run: bool) -> InstallReport {
+    let mut report = InstallReport::default();
+
+    for (plugin, spec) in config.spec.sourcing_order() {
+        match source_plugin(config, tmux, plugin, spec, dry_run) {
+            Ok(()) => report
+                .installed
+                .push((plugin.clone(), "sourced".to_string())),
+            Err(SourceError::Skipped(reason)) => report.skipped.push((plugin.clone(), reason)),
+            Err(SourceError::Failed(errors)) => {
+                report.failed.push((plugin.clone(), errors.join("\n")))
+            }
+        }
+    }
+
+    // NOTE: plugins changing the status line would otherwise only show up on the next redraw
+    if !dry_run && let Err(error) = tmux.refresh_client(None, true) {
+        log::debug!("Could not refresh the tmux client: {error}");
+    }
+
+    report.sort();
+    report
+}
+
+/// Reasons a plugin could not be sourced (completely).
+enum SourceError {
+    Skipped(String),
+    Failed(Vec<String>),
+}
+
+fn source_plugin(
+    config: &Config,
+    tmux: &Tmux,
+    plugin: &str,
+    spec: &PluginSpec,
+    dry_run: bool,
+) -> Result<(), SourceError> {
+    let plugin_dir = config.plugin_dir(plugin, spec);
+
+    if let Some(shell) = spec.shell()
+        && !is_executable_in_path(shell)
+    {
+        return Err(SourceError::Failed(vec![format!(
+            "Shell '{shell}' not found"
+        )]));
+    }
+
+    if !plugin_dir.is_dir() {
+        if dry_run {
+            println!("  [DRY RUN] Would source {plugin} once installed");
+            return Ok(());
+        }
+
+        let reason = format!("not installed at {}", plugin_dir.display());
+        return Err(SourceError::Skipped(reason));
+    }
+
+    let mode = spec.source_mode().unwrap_or(config.default_source_mode);
+    let shell = spec.shell();
+
+    if let Some(entry) = spec.entry() {
+        let entry_path = plugin_dir.join(entry);
+
+        if !dry_run && !entry_path.is_file() {
+            let error = format!("Entry file '{entry}' not found in {}", plugin_dir.display());
+            return Err(SourceError::Failed(vec![error]));
+        }
+
+        let result = match mode {
+            SourceMode::RunShell => run_file(tmux, &entry_path, shell, dry_run),
+            SourceMode::Auto | SourceMode::SourceFile => source_file(tmux, &entry_path, dry_run),
         };
 
-        // Check if this directory name is in the plugin spec
-        if !plugin_spec.plugins.contains_key(dir_name) {
-            // This is an orphaned plugin - remove it
-            let plugin_path = entry.path();
-            match fs::remove_dir_all(&plugin_path) {
-                Ok(_) => {
-                    println!("  Removed orphaned plugin: {}", dir_name);
-                }
-                Err(error) => {
-                    eprintln!(
-                        "  Failed to remove orphaned plugin '{}': {}",
-                        dir_name, error
-                    );
-                }
+        return result.map_err(|error| SourceError::Failed(vec![error]));
+    }
+
+    let mut entries: Vec<_> = match fs::read_dir(&plugin_dir) {
+        Ok(read_dir) => read_dir.flatten().collect(),
+        Err(error) => {
+            let reason = format!("could not read {}: {error}", plugin_dir.display());
+            return Err(SourceError::Skipped(reason));
+        }
+    };
+
+    // NOTE: directory listings are unordered, run the plugin's files in a stable order as well
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut errors = Vec::new();
+
+    if mode != SourceMode::RunShell
+        && let Some(plux_tmux) = entries
+            .iter()
+            .find(|entry| entry.file_name() == "plux.tmux")
+    {
+        match (source_file(tmux, &plux_tmux.path(), dry_run), mode) {
+            (Ok(()), _) => return Ok(()),
+            (Err(error), SourceMode::SourceFile) => return Err(SourceError::Failed(vec![error])),
+            // NOTE: if sourcing plux.tmux fails, fall back to running the plugin's *.tmux files
+            (Err(error), _) => errors.push(error),
+        }
+    }
+
+    // NOTE: sourced files are read by tmux itself, so they don't need to be executable
+    let require_executable = mode != SourceMode::SourceFile && shell.is_none();
+
+    for entry in entries
+        .iter()
+        .filter(|entry| is_runnable_tmux_file(entry, require_executable))
+    {
+        let result = match mode {
+            SourceMode::SourceFile => source_file(tmux, &entry.path(), dry_run),
+            SourceMode::Auto | SourceMode::RunShell => {
+                run_file(tmux, &entry.path(), shell, dry_run)
             }
+        };
+
+        if let Err(error) = result {
+            errors.push(error);
         }
     }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(SourceError::Failed(errors))
+    }
 }
 
-fn source_plugins(plugins_path: &Path, plugin_spec: &PluginSpecFile, tmux: &Tmux) {
-    let (stderr_tx, stderr_rx) = std::sync::mpsc::channel();
+/// Runs the given file with `tmux run-shell`, using `shell` instead of tmux's default shell if
+/// given, or only prints it in a dry run.
+fn run_file(tmux: &Tmux, path: &Path, shell: Option<&str>, dry_run: bool) -> Result<(), String> {
+    if dry_run {
+        println!("  [DRY RUN] Would run {}", path.display());
+        return Ok(());
+    }
 
-    std::thread::scope(move |scope| {
-        let (tx, rx) = std::sync::mpsc::channel();
+    log::debug!("Running {}", path.display());
+    // NOTE: waiting for every file keeps the plugin order and surfaces failing scripts. Scripts
+    // are not retried, their output is not captured and they might not be safe to run twice.
+    let result = match shell {
+        Some(shell) => tmux.run_shell_foreground_with(shell, path),
+        None => tmux.run_shell_foreground(path),
+    };
 
-        for plugin in plugin_spec.plugins.keys() {
-            let stderr = stderr_tx.clone();
-            let tx = tx.clone();
-            scope.spawn(move || {
-                let plugin_dir = plugins_path.join(plugin);
+    result.map_err(|error| error.to_string())
+}
 
-                let read_dir = fs::read_dir(&plugin_dir).unwrap();
-                let entries: Vec<_> = read_dir.into_iter().map(Result::unwrap).collect();
+/// Sources the given file with `tmux source-file`, or only prints it in a dry run.
+fn source_file(tmux: &Tmux, path: &Path, dry_run: bool) -> Result<(), String> {
+    if dry_run {
+        println!("  [DRY RUN] Would source {}", path.display());
+        return Ok(());
+    }
 
-                let plux_tmux_entry = entries.iter().find(|entry| {
-                    entry
-                        .path()
-                        .file_name()
-                        .is_some_and(|filename| filename == "plux.tmux")
-                });
+    log::debug!("Sourcing {}", path.display());
+    retry_transient(|| tmux.source_tmux(path)).map_err(|error| error.to_string())
+}
 
-                if let Some(plux_tmux) = plux_tmux_entry {
-                    match tmux.source_tmux(&plux_tmux.path()) {
-                        Err(error) => stderr.send(format!("{error}")).unwrap(),
-                        Ok(_) => return,
-                    }
-                }
+/// Prints the report of installing or sourcing plugins, one line per plugin.
+fn print_report(report: &InstallReport) {
+    for (plugin, description) in &report.installed {
+        log::info!("  [OK] {plugin} ({description})");
 
-                tx.send(entries).unwrap();
-            });
+        if let Some(stats) = report.clone_stats(plugin) {
+            log::debug!(
+                "    cloned in {:.1}s, {} on disk",
+                stats.duration.as_secs_f64(),
+                plux::disk::format_size(stats.size_bytes)
+            );
         }
+    }
 
-        drop(tx);
+    for (plugin, reason) in &report.skipped {
+        log::info!("  [SKIP] {plugin} ({reason})");
+    }
+
+    for (plugin, error) in &report.failed {
+        log::error!("  [ERROR] {plugin} - {error}");
+    }
+}
+
+/// Whether the given program exists, either as a path or as an executable found in `$PATH`.
+fn is_executable_in_path(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// Prints the outcome of the run as a single JSON object, along with the commit every installed
+/// plugin was resolved to.
+fn print_json_report(install: &InstallReport, source: &InstallReport, resolved: &LockFile) {
+    let report = serde_json::json!({
+        "success": !install.has_failures() && !source.has_failures(),
+        "install": install.to_json(),
+        "source": source.to_json(),
+        "plugins": resolved.plugins,
+    });
+
+    println!("{report}");
+}
 
-        while let Ok(entries) = rx.recv() {
-            for entry in entries
-                .into_iter()
-                .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "tmux"))
+/// Records the commit every installed plugin is checked out at.
+fn resolve_lock(config: &Config) -> LockFile {
+    let plugins = config
+        .spec
+        .plugins
+        .iter()
+        .filter_map(|(name, spec)| {
+            let plugin_dir = config.plugin_dir(name, spec);
+
+            // Plugins pinned to a commit may have had their history removed by `plux clean`
+            if plugin_dir.is_dir()
+                && !plugin_dir.join(".git").exists()
+                && let Some(Version::Commit(commit)) = spec.version()
             {
-                let stderr = stderr_tx.clone();
-                scope.spawn(move || {
-                    if let Err(error) = tmux.run_shell(&entry.path()) {
-                        stderr.send(format!("{error}")).unwrap();
+                return Some((
+                    name.clone(),
+                    LockedPlugin::new(spec, commit.trim().to_string()),
+                ));
+            }
+
+            let commit = config.git().for_repo(&plugin_dir).current_commit().ok()?;
+            Some((name.clone(), LockedPlugin::new(spec, commit)))
+        })
+        .collect();
+
+    LockFile { plugins }
+}
+
+fn update_plugins(config: &Config, options: &RunOptions) {
+    std::thread::scope(|s| {
+        let handles: Vec<_> = config
+            .spec
+            .plugins
+            .iter()
+            .map(|(plugin_name, plugin_spec)| {
+                s.spawn(move || {
+                    if let Some(version) = plugin_spec.pinned_version() {
+                        let msg = format!("  [SKIP] {plugin_name} (pinned to {version})");
+                        return (log::Level::Info, msg);
                     }
-                });
+
+                    let plugin_dir = config.plugin_dir(plugin_name, plugin_spec);
+                    if !plugin_dir.is_dir() {
+                        return (
+                            log::Level::Info,
+                            format!("  [SKIP] {plugin_name} (not installed)"),
+                        );
+                    }
+
+                    let git = config.git().for_repo(&plugin_dir);
+                    let url = plugin_spec.url();
+                    let moved = git
+                        .remote_url()
+                        .is_ok_and(|remote| normalize_url(&remote) != normalize_url(url));
+                    let moved_note = if moved {
+                        format!(", origin set to {url}")
+                    } else {
+                        String::new()
+                    };
+
+                    if options.dry_run {
+                        return (
+                            log::Level::Info,
+                            format!("  [DRY RUN] Would update {plugin_name}{moved_note}"),
+                        );
+                    }
+
+                    let before = git.current_commit();
+
+                    let result = if options.is_forced(plugin_name) {
+                        Ok(())
+                    } else {
+                        ensure_clean(&git, &plugin_dir)
+                    }
+                    // NOTE: the plugin moved to another URL, update it from there instead of
+                    // cloning it again
+                    .and_then(|_| {
+                        if moved {
+                            git.set_remote_url(url)?;
+                        }
+                        Ok(())
+                    })
+                    .and_then(|_| git.update().map_err(InstallError::from))
+                    .and_then(|_| plugin_spec.init_submodules(&git, &plugin_dir));
+
+                    match result {
+                        Ok(_) if before.ok() == git.current_commit().ok() => (
+                            log::Level::Info,
+                            format!("  [OK] {plugin_name} (up to date{moved_note})"),
+                        ),
+                        Ok(_) => (
+                            log::Level::Info,
+                            format!("  [OK] {plugin_name} (updated{moved_note})"),
+                        ),
+                        Err(error) => (
+                            log::Level::Error,
+                            format!("  [ERROR] {plugin_name} - Failed to update: {error}"),
+                        ),
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            if let Ok((level, msg)) = handle.join() {
+                log::log!(level, "{msg}");
             }
         }
+    });
+}
+
+/// Runs the tmux operation, retrying it a few times with a short delay if it fails with a
+/// transient error.
+fn retry_transient(
+    mut operation: impl FnMut() -> Result<(), murus::Error>,
+) -> Result<(), murus::Error> {
+    const MAX_ATTEMPTS: u32 = 3;
+    const RETRY_DELAY: Duration = Duration::from_millis(200);
 
-        drop(stderr_tx);
+    let mut attempt = 1;
 
-        while let Ok(error_msg) = stderr_rx.recv() {
-            eprintln!("{error_msg}");
+    loop {
+        match operation() {
+            Err(error) if error.is_transient() && attempt < MAX_ATTEMPTS => {
+                attempt += 1;
+                std::thread::sleep(RETRY_DELAY);
+            }
+            result => return result,
         }
-    });
+    }
 }
 
-fn install_plugins(plugins_path: &Path, plugin_spec: PluginSpecFile) {
+fn install_plugins(
+    config: &Config,
+    tmux: &Tmux,
+    lock: &LockFile,
+    options: &RunOptions,
+) -> InstallReport {
     enum Msg {
-        PluginReady(String, PluginSpec),
-        Stdout(String),
+        PluginReady(String, PluginSpec, CloneStats),
+        AlreadyInstalled(String),
+        Failed(String, String),
     }
 
+    let git = &config.git();
     let (tx, rx) = std::sync::mpsc::channel();
+    let mut report = InstallReport::default();
+
+    // Permits limiting the number of concurrent clones. A permit is taken before spawning an
+    // install thread and handed back once the thread is done cloning.
+    let (permit_tx, permit_rx) = std::sync::mpsc::sync_channel(config.max_parallel);
+    for _ in 0..config.max_parallel {
+        permit_tx.send(()).unwrap();
+    }
+
+    let mut progress = (!options.dry_run && log::log_enabled!(log::Level::Info))
+        .then(|| InstallProgress::new(tmux, config.spec.plugins.len()));
 
     std::thread::scope(|s| {
-        for (plugin_name, plugin_spec) in plugin_spec.plugins {
-            let tx = tx.clone();
-
-            s.spawn(move || {
-                let plugin_dir = plugins_path.join(&plugin_name);
-                match plugin_spec.try_install(&plugin_dir) {
-                    Ok(_) => tx.send(Msg::PluginReady(plugin_name, plugin_spec)).unwrap(),
-                    Err(InstallError::AlreadyInstalled) => {
-                        tx.send(Msg::Stdout(format!(
-                            "  [OK] {plugin_name} (already installed)"
-                        )))
-                        .unwrap();
-                    }
-                    Err(error) => {
-                        tx.send(Msg::Stdout(format!("Could not install plugin:\n{error}")))
-                            .unwrap();
+        // Spawn install threads from a separate thread, so that finished plugins are handled (and
+        // progress is shown) while waiting for permits
+        s.spawn(move || {
+            for (plugin_name, plugin_spec) in config.spec.plugins.clone() {
+                let plugin_dir = config.plugin_dir(&plugin_name, &plugin_spec);
+                let force = options.is_forced(&plugin_name);
+
+                if options.dry_run {
+                    if force && plugin_dir.exists() {
+                        println!(
+                            "  [DRY RUN] Would reinstall {} into {}",
+                            plugin_spec.url(),
+                            plugin_dir.display()
+                        );
+                    } else if plugin_dir.exists() {
+                        tx.send(Msg::AlreadyInstalled(plugin_name)).unwrap();
+                    } else {
+                        println!(
+                            "  [DRY RUN] Would clone {} into {}",
+                            plugin_spec.url(),
+                            plugin_dir.display()
+                        );
                     }
+                    continue;
                 }
-            });
-        }
 
-        drop(tx);
+                permit_rx.recv().unwrap();
 
-        while let Ok(msg) = rx.recv() {
-            match msg {
-                Msg::PluginReady(plugin_name, plugin_spec) => {
-                    // plugin successfully cloned, now let's try setting the version
-                    let plugin_dir = plugins_path.join(&plugin_name);
-                    match plugin_spec.choose_version(&plugin_dir) {
-                        Ok(installed_version) => {
-                            println!("  [OK] {plugin_name} ({installed_version})");
+                let tx = tx.clone();
+                let permit_tx = permit_tx.clone();
+
+                s.spawn(move || {
+                    // Show clone progress in tmux, throttled to avoid spawning a tmux command for
+                    // every progress line
+                    let mut last_progress: Option<Instant> = None;
+                    let mut on_progress = |line: &str| {
+                        if last_progress.is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL) {
+                            last_progress = Some(Instant::now());
+                            notify(tmux, &format!(" PLUX | {plugin_name}: {line}"), 1000);
                         }
-                        Err(error) => {
-                            eprintln!("  [ERROR] {plugin_name} - Failed to install: {error}");
+                    };
+
+                    let started = Instant::now();
+                    let result = if force {
+                        plugin_spec.reinstall(
+                            git,
+                            &plugin_dir,
+                            config.clone_retries,
+                            &mut on_progress,
+                        )
+                    } else {
+                        plugin_spec.try_install(
+                            git,
+                            &plugin_dir,
+                            config.clone_retries,
+                            &mut on_progress,
+                        )
+                    };
+
+                    let msg = match result {
+                        Ok(_) => {
+                            let stats = CloneStats {
+                                duration: started.elapsed(),
+                                size_bytes: plux::disk::dir_size(&plugin_dir),
+                            };
+                            Msg::PluginReady(plugin_name, plugin_spec, stats)
                         }
+                        Err(InstallError::AlreadyInstalled) => Msg::AlreadyInstalled(plugin_name),
+                        Err(error) => Msg::Failed(plugin_name, error.to_string()),
+                    };
+
+                    let _ = permit_tx.send(());
+                    tx.send(msg).unwrap();
+                });
+            }
+        });
+
+        loop {
+            let msg = match rx.recv_timeout(SPINNER_INTERVAL) {
+                Ok(msg) => msg,
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(progress) = &mut progress {
+                        progress.tick();
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            match msg {
+                Msg::PluginReady(plugin_name, plugin_spec, stats) => {
+                    report.clone_stats.push((plugin_name.clone(), stats));
+
+                    // plugin successfully cloned, now let's try setting the version
+                    let plugin_dir = config.plugin_dir(&plugin_name, &plugin_spec);
+                    let result = match lock.locked_commit(&plugin_name, &plugin_spec) {
+                        Some(commit) => plugin_spec.choose_locked_version(git, &plugin_dir, commit),
+                        None => plugin_spec.choose_version(git, &plugin_dir),
                     }
+                    .and_then(|version| {
+                        plugin_spec.init_submodules(git, &plugin_dir)?;
+                        plugin_spec.run_post_install(&plugin_dir)?;
+                        Ok(version)
+                    });
+
+                    match result {
+                        Ok(installed_version) => report
+                            .installed
+                            .push((plugin_name, installed_version.to_string())),
+                        Err(error) => report.failed.push((plugin_name, error.to_string())),
+                    }
+                }
+                Msg::AlreadyInstalled(plugin_name) => {
+                    report
+                        .skipped
+                        .push((plugin_name, "already installed".to_string()));
                 }
-                Msg::Stdout(msg) => println!("{msg}"),
+                Msg::Failed(plugin_name, error) => report.failed.push((plugin_name, error)),
+            }
+
+            if let Some(progress) = &mut progress {
+                progress.advance();
             }
         }
     });
+
+    if let Some(progress) = &progress {
+        progress.finish();
+    }
+
+    report.sort();
+    report
 }
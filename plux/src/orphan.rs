@@ -0,0 +1,178 @@
+//! Detection of orphaned plugin directories
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::git::Git;
+use crate::plugin::normalize_url;
+
+/// Finds plugin directories that are not part of the plugin spec. Returned paths are relative to
+/// the plugins directory.
+pub fn find_orphans(config: &Config) -> Vec<PathBuf> {
+    let mut orphans = Vec::new();
+
+    // If plugins directory doesn't exist, there are no orphans
+    if !config.plugins_path.exists() {
+        return orphans;
+    }
+
+    let expected: HashSet<PathBuf> = config
+        .spec
+        .plugins
+        .iter()
+        .map(|(name, spec)| config.layout.relative_dir(name, spec))
+        .collect();
+
+    collect_orphans(&config.plugins_path, Path::new(""), &expected, &mut orphans);
+
+    orphans.sort();
+    orphans
+}
+
+/// Recursively collects directories in `dir` that neither are nor contain an expected plugin
+/// directory. `relative` is the path of `dir` relative to the plugins directory.
+fn collect_orphans(
+    dir: &Path,
+    relative: &Path,
+    expected: &HashSet<PathBuf>,
+    orphans: &mut Vec<PathBuf>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        log::warn!("Could not read plugins directory at {}", dir.display());
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        // Only consider directories
+        if !file_type.is_dir() {
+            continue;
+        }
+
+        let entry_relative = relative.join(entry.file_name());
+
+        if expected.contains(&entry_relative) {
+            continue;
+        }
+
+        // Intermediate directory of a nested layout, look for orphans inside of it
+        if expected
+            .iter()
+            .any(|path| path.starts_with(&entry_relative))
+        {
+            collect_orphans(&entry.path(), &entry_relative, expected, orphans);
+            continue;
+        }
+
+        orphans.push(entry_relative);
+    }
+}
+
+/// Checks whether the directory looks like a plugin plux cloned, i.e. a git repository with an
+/// `origin` remote. Anything else is not plux's to delete.
+pub fn is_plugin_dir(git: &Git, path: &Path) -> bool {
+    path.join(".git").exists() && git.for_repo(path).remote_url().is_ok()
+}
+
+/// Finds orphans cloned from the same URL as a plugin that is not installed yet, i.e. plugins
+/// that were renamed in the spec file and can be moved instead of being cloned again. Maps every
+/// such orphan to the directory of the plugin it belongs to.
+pub fn find_renamed(config: &Config, orphans: &[PathBuf]) -> HashMap<PathBuf, PathBuf> {
+    // Plugins that are not installed yet, keyed by URL
+    let mut pending: HashMap<String, PathBuf> = config
+        .spec
+        .plugins
+        .iter()
+        .map(|(name, spec)| (spec, config.plugin_dir(name, spec)))
+        .filter(|(_, dir)| !dir.exists())
+        .map(|(spec, dir)| (normalize_url(spec.url()), dir))
+        .collect();
+
+    let git = config.git();
+
+    orphans
+        .iter()
+        .filter_map(|orphan| {
+            let url = git
+                .for_repo(config.plugins_path.join(orphan))
+                .remote_url()
+                .ok()?;
+            let destination = pending.remove(&normalize_url(&url))?;
+            Some((orphan.clone(), destination))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{TempDir, config, git, init_repo};
+
+    const SPEC: &str = r#"
+        [plugins]
+        tmux-yank = "https://github.com/tmux-plugins/tmux-yank"
+    "#;
+
+    /// Creates a cloned plugin in the plugins directory with the given `origin` URL
+    fn install_orphan(config: &Config, name: &str, url: &str) {
+        let dir = config.plugins_path.join(name);
+        init_repo(&dir);
+        git(&dir, &["remote", "add", "origin", url]);
+    }
+
+    #[test]
+    fn orphan_with_url_of_new_plugin_is_renamed() {
+        let dir = TempDir::new();
+        let config = config(dir.path(), SPEC);
+        install_orphan(
+            &config,
+            "yank",
+            "https://github.com/tmux-plugins/tmux-yank.git",
+        );
+
+        let orphans = find_orphans(&config);
+        assert_eq!(orphans, [PathBuf::from("yank")]);
+
+        let renamed = find_renamed(&config, &orphans);
+        assert_eq!(
+            renamed,
+            HashMap::from([(PathBuf::from("yank"), config.plugins_path.join("tmux-yank"))])
+        );
+    }
+
+    #[test]
+    fn orphan_with_other_url_is_not_renamed() {
+        let dir = TempDir::new();
+        let config = config(dir.path(), SPEC);
+        install_orphan(
+            &config,
+            "copycat",
+            "https://github.com/tmux-plugins/tmux-copycat",
+        );
+
+        let orphans = find_orphans(&config);
+
+        assert!(find_renamed(&config, &orphans).is_empty());
+    }
+
+    #[test]
+    fn orphan_is_not_renamed_onto_installed_plugin() {
+        let dir = TempDir::new();
+        let config = config(dir.path(), SPEC);
+        install_orphan(&config, "yank", "https://github.com/tmux-plugins/tmux-yank");
+        install_orphan(
+            &config,
+            "tmux-yank",
+            "https://github.com/tmux-plugins/tmux-yank",
+        );
+
+        let orphans = find_orphans(&config);
+
+        assert!(find_renamed(&config, &orphans).is_empty());
+    }
+}
@@ -1,15 +1,238 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+
+use crate::git::Git;
 
 pub const DEFAULT_PLUGINS_PATH: &str = "$HOME/.config/tmux/plux/";
 pub const DEFAULT_SPEC_PATH: &str = "$HOME/.config/tmux/plux.toml";
+/// Host used for shorthand `owner/repo` plugin URLs.
+pub const DEFAULT_HOST: &str = "github.com";
+
+/// Directory structure used for plugins inside the plugins directory.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Every plugin is cloned into a directory named after its key in the spec file, with
+    /// characters that don't belong in a directory name (e.g. slashes) replaced.
+    #[default]
+    Flat,
+    /// Plugins are cloned into `host/owner/repo` directories derived from their URL.
+    Nested,
+}
+
+impl Layout {
+    /// Returns the directory, relative to the plugins directory, where the plugin with the given
+    /// name and specification is installed.
+    pub fn relative_dir(self, name: &str, spec: &PluginSpec) -> PathBuf {
+        match self {
+            Layout::Flat => PathBuf::from(sanitize_dir_name(name)),
+            Layout::Nested => repo_path_from_url(spec.url())
+                .unwrap_or_else(|| PathBuf::from(sanitize_dir_name(name))),
+        }
+    }
+
+    /// Returns the directory where the plugin with the given name and specification is installed.
+    pub fn plugin_dir(self, plugins_path: &Path, name: &str, spec: &PluginSpec) -> PathBuf {
+        plugins_path.join(self.relative_dir(name, spec))
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "flat" => Ok(Layout::Flat),
+            "nested" => Ok(Layout::Nested),
+            other => Err(other.to_string()),
+        }
+    }
+}
+
+/// How the files of a plugin are loaded into tmux.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceMode {
+    /// `plux.tmux` is sourced via `source-file` if the plugin has one, otherwise the plugin's
+    /// executable `*.tmux` files are run via `run-shell`.
+    #[default]
+    Auto,
+    /// `plux.tmux` is sourced via `source-file` if the plugin has one, otherwise all of the
+    /// plugin's `*.tmux` files are, for plugins that expect to be sourced synchronously.
+    SourceFile,
+    /// The plugin's executable `*.tmux` files are run via `run-shell`, even if it has a
+    /// `plux.tmux`.
+    RunShell,
+}
+
+impl SourceMode {
+    /// Returns the name of the mode, as written in the spec file and tmux options.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SourceMode::Auto => "auto",
+            SourceMode::SourceFile => "source_file",
+            SourceMode::RunShell => "run_shell",
+        }
+    }
+}
+
+impl FromStr for SourceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "auto" => Ok(SourceMode::Auto),
+            "source_file" => Ok(SourceMode::SourceFile),
+            "run_shell" => Ok(SourceMode::RunShell),
+            other => Err(other.to_string()),
+        }
+    }
+}
+
+/// Turns a plugin name into a single directory name, replacing path separators and whitespace with
+/// `_`, so a name like `../foo` or `my plugin` can't escape the plugins directory or get mangled.
+fn sanitize_dir_name(name: &str) -> String {
+    let sanitized: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' => '_',
+            c if c.is_whitespace() => '_',
+            c => c,
+        })
+        .collect();
+
+    match sanitized.as_str() {
+        "" | "." | ".." => "_".repeat(sanitized.len().max(1)),
+        _ => sanitized,
+    }
+}
+
+/// Derives a `host/owner/repo` path from a git URL. Supports URLs with a scheme (e.g.
+/// `https://github.com/owner/repo.git`) and scp-like SSH URLs (e.g.
+/// `git@github.com:owner/repo.git`).
+fn repo_path_from_url(url: &str) -> Option<PathBuf> {
+    let url = url.trim();
+
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.to_string(),
+        // scp-like syntax uses ':' to separate host from path
+        None => url.replacen(':', "/", 1),
+    };
+
+    let (host, path) = rest.split_once('/')?;
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    let mut components = vec![host];
+    components.extend(path.split('/').filter(|c| !c.is_empty()));
+
+    let is_valid = components.len() >= 2
+        && components
+            .iter()
+            .all(|c| !c.is_empty() && *c != "." && *c != "..");
+
+    is_valid.then(|| components.iter().collect())
+}
+
+/// Expands a shorthand `owner/repo` URL into a full URL on the given host, e.g.
+/// `https://github.com/owner/repo`. The host may include a scheme, otherwise `https` is used.
+/// Returns `None` if the URL is not a shorthand.
+fn expand_shorthand(url: &str, host: &str) -> Option<String> {
+    let url = url.trim();
+
+    if url.contains(':') || url.contains('@') {
+        return None;
+    }
+
+    let (owner, repo) = url.split_once('/')?;
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None;
+    }
+
+    let host = host.trim().trim_end_matches('/');
+    let base = if host.contains("://") {
+        host.to_string()
+    } else {
+        format!("https://{host}")
+    };
+
+    Some(format!("{base}/{owner}/{repo}"))
+}
+
+/// Normalizes a git URL so that URLs pointing to the same repository compare equal.
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url).to_string()
+}
+
+/// Checks whether the URL is a git URL that can be cloned: either a URL with a supported scheme
+/// (e.g. `https://github.com/owner/repo`) or an scp-like SSH URL (e.g.
+/// `git@github.com:owner/repo.git`), in both cases with a non-empty host.
+fn is_valid_url(url: &str) -> bool {
+    const SCHEMES: [&str; 4] = ["https", "http", "ssh", "git"];
+
+    let url = url.trim();
+
+    let host = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            if !SCHEMES.contains(&scheme) {
+                return false;
+            }
+
+            let authority = rest.split('/').next().unwrap_or_default();
+            let host = authority.rsplit('@').next().unwrap_or_default();
+            host.split(':').next().unwrap_or_default()
+        }
+        None => match url.split_once(':') {
+            Some((user_and_host, path)) if !path.is_empty() => {
+                match user_and_host.split_once('@') {
+                    Some((user, host)) if !user.is_empty() => host,
+                    _ => return false,
+                }
+            }
+            _ => return false,
+        },
+    };
+
+    !host.is_empty() && !host.contains(char::is_whitespace)
+}
 
 /// Models the TOML file used to specify plugins to install. See [`PluginSpec`] for more
-/// information.
-#[derive(Clone, serde::Deserialize)]
+/// information. Plugins of other spec files can be merged in with `include`:
+///
+/// ```toml
+/// include = ["work.toml", "~/dotfiles/tmux/base.toml"]
+/// ```
+#[derive(Clone, Default, serde::Deserialize)]
 pub struct PluginSpecFile {
+    /// Other spec files whose plugins are merged into this one. Relative paths are resolved
+    /// relative to the including file.
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    #[serde(default)]
     pub plugins: HashMap<String, PluginSpec>,
 }
 
+impl PluginSpecFile {
+    /// Returns the plugins in the order they are sourced: by ascending priority, plugins with
+    /// the same priority sorted by name.
+    pub fn sourcing_order(&self) -> Vec<(&String, &PluginSpec)> {
+        let mut plugins: Vec<_> = self.plugins.iter().collect();
+        plugins.sort_by_key(|(name, spec)| (spec.priority(), *name));
+        plugins
+    }
+}
+
 /// Models supported version specifiers for a plugin.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -45,6 +268,16 @@ impl std::fmt::Display for Version {
 /// second = { url = "...", branch = "main" }
 /// # commit hash as version
 /// third = { url = "...", commit = "<commit hash>" }
+/// # custom shell for running the plugin's *.tmux files
+/// fourth = { url = "...", shell = "bash" }
+/// # initialize git submodules after cloning
+/// fifth = { url = "...", submodules = true }
+/// # build step run in the plugin directory after installing it
+/// sixth = { url = "...", post_install = "make" }
+/// # sourced after plugins with a lower priority
+/// seventh = { url = "...", priority = 10 }
+/// # sourced via source-file instead of run-shell
+/// eighth = { url = "...", source_mode = "source_file" }
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize)]
 pub struct FullPluginSpec {
@@ -54,6 +287,30 @@ pub struct FullPluginSpec {
     /// Optional version specification for the given plugin.
     #[serde(flatten)]
     pub tag_or_commit: Option<Version>,
+
+    /// Optional shell used to run the plugin's `*.tmux` files, e.g. `bash`. By default, tmux
+    /// runs them with its default shell.
+    pub shell: Option<String>,
+
+    /// Whether git submodules of the plugin should be initialized after cloning it.
+    #[serde(default)]
+    pub submodules: bool,
+
+    /// Optional shell command run in the plugin directory after the plugin was freshly
+    /// installed, e.g. a build step like `make`.
+    pub post_install: Option<String>,
+
+    /// Optional file, relative to the plugin directory, sourced instead of `plux.tmux` or the
+    /// plugin's `*.tmux` files, for plugins that don't follow TPM conventions.
+    pub entry: Option<String>,
+
+    /// Optional priority of the plugin when sourcing plugins. Plugins with a lower priority are
+    /// sourced first, the default priority is `0`.
+    pub priority: Option<i32>,
+
+    /// Optional way the plugin's files are loaded into tmux, overriding
+    /// `@plux_default_source_mode`.
+    pub source_mode: Option<SourceMode>,
 }
 
 /// Errors that can occur during installation of plugin.
@@ -66,6 +323,58 @@ pub enum InstallError {
     /// An error occurred during git operations
     #[error("Git operation failed: {0}")]
     Git(#[from] crate::git::GitError),
+
+    /// The existing plugin directory could not be removed before reinstalling the plugin.
+    #[error("Could not remove existing plugin directory: {0}")]
+    Removal(#[from] std::io::Error),
+
+    /// The plugin directory has local changes that checking out another version could discard.
+    #[error("Plugin directory {0} has uncommitted changes, use --force to proceed anyway")]
+    DirtyWorkingTree(String),
+
+    /// The post-install hook of the plugin failed.
+    #[error("Post-install hook '{command}' failed:\n{stderr}")]
+    HookFailed { command: String, stderr: String },
+}
+
+/// Fails with [`InstallError::DirtyWorkingTree`] if the plugin directory has uncommitted changes,
+/// so local modifications of a plugin are not silently clobbered.
+pub fn ensure_clean(git: &Git, destination_dir: &Path) -> Result<(), InstallError> {
+    if git.for_repo(destination_dir).is_dirty()? {
+        return Err(InstallError::DirtyWorkingTree(
+            destination_dir.display().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether the entry of a plugin directory is a `*.tmux` file that should be loaded. Like TPM,
+/// hidden files are skipped, and files have to be executable if `require_executable` is set, i.e.
+/// unless they are sourced or the plugin specifies a shell to run them with.
+pub fn is_runnable_tmux_file(entry: &fs::DirEntry, require_executable: bool) -> bool {
+    let path = entry.path();
+    if entry.file_name().to_string_lossy().starts_with('.')
+        || path.extension().is_none_or(|ext| ext != "tmux")
+    {
+        return false;
+    }
+
+    // NOTE: symlinks are followed, so linked scripts are run as well
+    let Ok(metadata) = fs::metadata(&path) else {
+        return false;
+    };
+
+    if !metadata.is_file() {
+        return false;
+    }
+
+    if require_executable && metadata.permissions().mode() & 0o111 == 0 {
+        log::debug!("Skipping {}: not executable", path.display());
+        return false;
+    }
+
+    true
 }
 
 /// Models specification of a single plugin. This can either be URL-only, or full plugin
@@ -78,7 +387,7 @@ pub enum PluginSpec {
 }
 
 impl PluginSpec {
-    /// Returns the URL specified for this plugin as.
+    /// Returns the URL specified for this plugin.
     pub fn url(&self) -> &str {
         match self {
             PluginSpec::Url(url) => url,
@@ -86,42 +395,571 @@ impl PluginSpec {
         }
     }
 
+    /// Expands a shorthand `owner/repo` URL of this plugin into a full URL on the given host.
+    /// Other URLs, including SSH URLs, are left untouched.
+    pub fn expand_shorthand_url(&mut self, host: &str) {
+        let url = match self {
+            PluginSpec::Url(url) => url,
+            PluginSpec::Full(full_plugin_spec) => &mut full_plugin_spec.url,
+        };
+
+        if let Some(expanded) = expand_shorthand(url, host) {
+            *url = expanded;
+        }
+    }
+
+    /// Checks whether the URL of this plugin looks like a git URL that can be cloned, i.e. uses
+    /// the `https://`, `http://`, `ssh://` or `git://` scheme or the scp-like `git@host:path`
+    /// syntax and has a non-empty host.
+    pub fn has_valid_url(&self) -> bool {
+        is_valid_url(self.url())
+    }
+
+    /// Converts this plugin specification into a TOML value as written in the spec file: a
+    /// string for URL-only plugins and an inline table otherwise.
+    pub fn to_toml_value(&self) -> toml_edit::Value {
+        let full = match self {
+            PluginSpec::Url(url) => return url.into(),
+            PluginSpec::Full(full_plugin_spec) => full_plugin_spec,
+        };
+
+        let mut table = toml_edit::InlineTable::new();
+        table.insert("url", full.url.as_str().into());
+
+        match &full.tag_or_commit {
+            Some(Version::Tag(tag)) => table.insert("tag", tag.as_str().into()),
+            Some(Version::Commit(commit)) => table.insert("commit", commit.as_str().into()),
+            Some(Version::Branch(branch)) => table.insert("branch", branch.as_str().into()),
+            None => None,
+        };
+
+        if let Some(shell) = &full.shell {
+            table.insert("shell", shell.as_str().into());
+        }
+
+        if full.submodules {
+            table.insert("submodules", true.into());
+        }
+
+        if let Some(post_install) = &full.post_install {
+            table.insert("post_install", post_install.as_str().into());
+        }
+
+        if let Some(entry) = &full.entry {
+            table.insert("entry", entry.as_str().into());
+        }
+
+        if let Some(priority) = full.priority {
+            table.insert("priority", i64::from(priority).into());
+        }
+
+        if let Some(source_mode) = full.source_mode {
+            table.insert("source_mode", source_mode.as_str().into());
+        }
+
+        table.into()
+    }
+
+    /// Returns the version specified for this plugin, if any.
+    pub fn version(&self) -> Option<&Version> {
+        match self {
+            PluginSpec::Url(_) => None,
+            PluginSpec::Full(full_plugin_spec) => full_plugin_spec.tag_or_commit.as_ref(),
+        }
+    }
+
+    /// Returns the version this plugin is pinned to, if it is pinned to a tag or a commit.
+    /// Plugins following a branch are not pinned.
+    pub fn pinned_version(&self) -> Option<&Version> {
+        match self {
+            PluginSpec::Full(FullPluginSpec {
+                tag_or_commit: Some(version @ (Version::Tag(_) | Version::Commit(_))),
+                ..
+            }) => Some(version),
+            _ => None,
+        }
+    }
+
+    /// Returns the shell used to run this plugin's `*.tmux` files, if one was specified.
+    pub fn shell(&self) -> Option<&str> {
+        match self {
+            PluginSpec::Url(_) => None,
+            PluginSpec::Full(full_plugin_spec) => full_plugin_spec.shell.as_deref(),
+        }
+    }
+
+    /// Returns the file sourced instead of the plugin's `plux.tmux` or `*.tmux` files, if one was
+    /// specified.
+    pub fn entry(&self) -> Option<&str> {
+        match self {
+            PluginSpec::Url(_) => None,
+            PluginSpec::Full(full_plugin_spec) => full_plugin_spec.entry.as_deref(),
+        }
+    }
+
+    /// Returns the way this plugin's files are loaded into tmux, if one was specified.
+    pub fn source_mode(&self) -> Option<SourceMode> {
+        match self {
+            PluginSpec::Url(_) => None,
+            PluginSpec::Full(full_plugin_spec) => full_plugin_spec.source_mode,
+        }
+    }
+
+    /// Returns the priority of this plugin when sourcing plugins, `0` unless specified.
+    pub fn priority(&self) -> i32 {
+        match self {
+            PluginSpec::Url(_) => 0,
+            PluginSpec::Full(full_plugin_spec) => full_plugin_spec.priority.unwrap_or_default(),
+        }
+    }
+
+    /// Initializes the plugin's git submodules, if enabled in its specification.
+    pub fn init_submodules(&self, git: &Git, destination_dir: &Path) -> Result<(), InstallError> {
+        if let PluginSpec::Full(FullPluginSpec {
+            submodules: true, ..
+        }) = self
+        {
+            git.for_repo(destination_dir).update_submodules()?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the post-install hook of this plugin in its directory, if one was specified.
+    pub fn run_post_install(&self, destination_dir: &Path) -> Result<(), InstallError> {
+        let PluginSpec::Full(FullPluginSpec {
+            post_install: Some(command),
+            ..
+        }) = self
+        else {
+            return Ok(());
+        };
+
+        log::debug!("sh -c {command} (in {})", destination_dir.display());
+        let output = Command::new("sh")
+            .args(["-c", command])
+            .current_dir(destination_dir)
+            .output()
+            .map_err(|e| InstallError::HookFailed {
+                command: command.clone(),
+                stderr: e.to_string(),
+            })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(InstallError::HookFailed {
+                command: command.clone(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            })
+        }
+    }
+
     /// Tries to install plugin at the provided path. This involves cloning the git repository if
-    /// it's not already installed.
-    pub fn try_install(&self, destination_dir: &Path) -> Result<(), InstallError> {
+    /// it's not already installed. Plugins pinned to a commit are cloned with their full history,
+    /// since an arbitrary commit is usually not part of a shallow clone, while only the requested
+    /// branch or tag is cloned for plugins following one. Cloning is attempted up to
+    /// `attempts` times when it fails due to network issues. Clone progress is passed to
+    /// `on_progress` line by line.
+    pub fn try_install(
+        &self,
+        git: &Git,
+        destination_dir: &Path,
+        attempts: u32,
+        on_progress: &mut dyn FnMut(&str),
+    ) -> Result<(), InstallError> {
         if destination_dir.is_dir() {
             return Err(InstallError::AlreadyInstalled);
         }
 
-        let git = crate::git::Git::default();
-        git.clone_shallow(self.url(), destination_dir)?;
+        match self.version() {
+            Some(Version::Commit(_)) => {
+                git.clone_full_with_retries(self.url(), destination_dir, attempts, on_progress)?
+            }
+            // Git accepts tags for --branch as well, so only the requested version is fetched
+            Some(Version::Branch(name) | Version::Tag(name)) => git
+                .clone_shallow_branch_with_retries(
+                    self.url(),
+                    destination_dir,
+                    name,
+                    attempts,
+                    on_progress,
+                )?,
+            None => {
+                git.clone_shallow_with_retries(self.url(), destination_dir, attempts, on_progress)?
+            }
+        }
 
         Ok(())
     }
 
-    /// Determines the version of plugin that should be used and tries to choose that version.
-    pub fn choose_version(&self, destination_dir: &Path) -> Result<Version, InstallError> {
-        let git = crate::git::Git::in_repo(destination_dir);
+    /// Installs the plugin from scratch, removing the existing plugin directory first if there is
+    /// one. See [`PluginSpec::try_install`] for details.
+    pub fn reinstall(
+        &self,
+        git: &Git,
+        destination_dir: &Path,
+        attempts: u32,
+        on_progress: &mut dyn FnMut(&str),
+    ) -> Result<(), InstallError> {
+        if destination_dir.exists() {
+            std::fs::remove_dir_all(destination_dir)?;
+        }
 
-        git.fetch_tags()?;
+        self.try_install(git, destination_dir, attempts, on_progress)
+    }
 
-        let version = if let PluginSpec::Full(full_plugin_spec) = self
-            && let Some(tag_or_commit) = &full_plugin_spec.tag_or_commit
-        {
-            tag_or_commit
-        } else {
-            let branch = git.get_default_branch()?;
-            &Version::Branch(branch)
+    /// Determines the version of plugin that should be used and tries to choose that version.
+    pub fn choose_version(
+        &self,
+        git: &Git,
+        destination_dir: &Path,
+    ) -> Result<Version, InstallError> {
+        let git = git.for_repo(destination_dir);
+
+        // Only fetch what is needed for the requested version, fetching every tag of a repository
+        // can take a long time
+        let version = match self.version() {
+            Some(Version::Tag(tag)) => {
+                git.fetch_ref(&format!("refs/tags/{}", tag.trim()))?;
+                git.checkout(tag)?;
+                Version::Tag(tag.clone())
+            }
+            Some(Version::Branch(branch)) => {
+                git.fetch_ref(&format!("refs/heads/{}", branch.trim()))?;
+                git.checkout(branch)?;
+                Version::Branch(branch.clone())
+            }
+            Some(Version::Commit(commit)) => {
+                if git.checkout(commit).is_err() {
+                    git.fetch_commit(commit)?;
+                    git.checkout(commit)?;
+                }
+                Version::Commit(commit.clone())
+            }
+            None => {
+                git.fetch_tags()?;
+                let branch = git.get_default_branch()?;
+                git.checkout(&branch)?;
+                Version::Branch(branch)
+            }
         };
 
-        let version_str = match version {
-            Version::Tag(tag) => tag,
-            Version::Commit(commit) => commit,
-            Version::Branch(branch) => branch,
+        Ok(version)
+    }
+
+    /// Checks out the exact commit recorded in the lockfile, fetching it first if it is missing
+    /// from the (shallow) clone. Plugins following a branch stay on that branch, reset to the
+    /// locked commit, so they can still be updated later.
+    pub fn choose_locked_version(
+        &self,
+        git: &Git,
+        destination_dir: &Path,
+        commit: &str,
+    ) -> Result<Version, InstallError> {
+        let git = git.for_repo(destination_dir);
+
+        let branch = match self.version() {
+            Some(Version::Branch(branch)) => {
+                git.fetch_ref(&format!("refs/heads/{}", branch.trim()))?;
+                Some(branch.clone())
+            }
+            Some(Version::Tag(_) | Version::Commit(_)) => None,
+            None => Some(git.get_default_branch()?),
         };
 
-        git.checkout(version_str)?;
+        if git.resolve_commit(commit).is_err() {
+            git.fetch_commit(commit)?;
+        }
+
+        match branch {
+            Some(branch) => {
+                git.checkout(&branch)?;
+                git.reset_hard(commit)?;
+            }
+            None => git.checkout(commit)?,
+        }
+
+        Ok(Version::Commit(commit.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{Git, GitError};
+    use crate::test_util::{TempDir, git, init_repo};
+
+    fn url_spec(url: &str) -> PluginSpec {
+        PluginSpec::Url(url.to_string())
+    }
+
+    /// Parses the specification of a single plugin, as written in a spec file
+    fn spec(spec: &str) -> PluginSpec {
+        let mut file: PluginSpecFile =
+            toml::from_str(&format!("[plugins]\nplugin = {spec}")).unwrap();
+        file.plugins.remove("plugin").unwrap()
+    }
+
+    /// Creates a repository with three commits in `dir`, returning a `file://` URL to it, so
+    /// that clones of it can be shallow, and the hashes of the commits, oldest first
+    fn remote_repo(dir: &Path) -> (String, Vec<String>) {
+        init_repo(dir);
+        git(dir, &["commit", "--quiet", "--allow-empty", "-m", "second"]);
+        git(dir, &["commit", "--quiet", "--allow-empty", "-m", "third"]);
+
+        let commits = git(dir, &["rev-list", "--reverse", "HEAD"])
+            .lines()
+            .map(str::to_string)
+            .collect();
+
+        (format!("file://{}", dir.display()), commits)
+    }
+
+    #[test]
+    fn flat_layout_uses_plugin_name() {
+        let spec = url_spec("https://github.com/tmux-plugins/tmux-yank");
+
+        assert_eq!(
+            Layout::Flat.plugin_dir(Path::new("/plugins"), "yank", &spec),
+            PathBuf::from("/plugins/yank")
+        );
+    }
+
+    #[test]
+    fn nested_layout_uses_repository_path() {
+        let cases = [
+            (
+                "https://github.com/tmux-plugins/tmux-yank",
+                "github.com/tmux-plugins/tmux-yank",
+            ),
+            (
+                "https://github.com/tmux-plugins/tmux-yank.git/",
+                "github.com/tmux-plugins/tmux-yank",
+            ),
+            (
+                "ssh://git@gitlab.com:2222/group/sub/repo.git",
+                "gitlab.com/group/sub/repo",
+            ),
+            ("git@github.com:owner/repo.git", "github.com/owner/repo"),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(
+                Layout::Nested.plugin_dir(Path::new("/plugins"), "name", &url_spec(url)),
+                Path::new("/plugins").join(expected),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn nested_layout_falls_back_to_plugin_name() {
+        let spec = url_spec("https://github.com/../repo");
+
+        assert_eq!(
+            Layout::Nested.plugin_dir(Path::new("/plugins"), "name", &spec),
+            PathBuf::from("/plugins/name")
+        );
+    }
+
+    #[test]
+    fn shallow_clone_cannot_check_out_old_commit() {
+        let dir = TempDir::new();
+        let (url, commits) = remote_repo(&dir.path().join("remote"));
+        let destination = dir.path().join("plugin");
+
+        let git = Git::default();
+        git.clone_shallow(&url, &destination, &mut |_| {}).unwrap();
+
+        assert!(matches!(
+            git.for_repo(&destination).checkout(&commits[0]),
+            Err(GitError::CommandFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn install_checks_out_old_commit() {
+        let dir = TempDir::new();
+        let (url, commits) = remote_repo(&dir.path().join("remote"));
+        let destination = dir.path().join("plugin");
+        let plugin = spec(&format!(
+            r#"{{ url = "{url}", commit = "{}" }}"#,
+            commits[0]
+        ));
+
+        let git = Git::default();
+        plugin
+            .try_install(&git, &destination, 1, &mut |_| {})
+            .unwrap();
+        let version = plugin.choose_version(&git, &destination).unwrap();
+
+        assert_eq!(version, Version::Commit(commits[0].clone()));
+        assert_eq!(
+            git.for_repo(&destination).current_commit().unwrap(),
+            commits[0]
+        );
+    }
+
+    #[test]
+    fn install_fails_for_unknown_commit() {
+        let dir = TempDir::new();
+        let (url, _) = remote_repo(&dir.path().join("remote"));
+        let destination = dir.path().join("plugin");
+        let plugin = spec(&format!(
+            r#"{{ url = "{url}", commit = "0123456789abcdef0123456789abcdef01234567" }}"#
+        ));
+
+        let git = Git::default();
+        plugin
+            .try_install(&git, &destination, 1, &mut |_| {})
+            .unwrap();
+
+        assert!(matches!(
+            plugin.choose_version(&git, &destination),
+            Err(InstallError::Git(GitError::CommandFailed { .. }))
+        ));
+    }
+
+    #[test]
+    fn locked_commit_keeps_branch_plugin_on_branch() {
+        let dir = TempDir::new();
+        let (url, commits) = remote_repo(&dir.path().join("remote"));
+        let destination = dir.path().join("plugin");
+        let plugin = url_spec(&url);
+
+        let git = Git::default();
+        plugin
+            .try_install(&git, &destination, 1, &mut |_| {})
+            .unwrap();
+        plugin
+            .choose_locked_version(&git, &destination, &commits[1])
+            .unwrap();
+
+        let repo = git.for_repo(&destination);
+        assert_eq!(
+            crate::test_util::git(&destination, &["branch", "--show-current"]),
+            "main"
+        );
+        assert_eq!(repo.current_commit().unwrap(), commits[1]);
+
+        repo.update().unwrap();
+        assert_eq!(repo.current_commit().unwrap(), commits[2]);
+    }
+
+    #[test]
+    fn locked_commit_detaches_tag_plugin() {
+        let dir = TempDir::new();
+        let (url, commits) = remote_repo(&dir.path().join("remote"));
+        git(&dir.path().join("remote"), &["tag", "v1.0.0", &commits[1]]);
+        let destination = dir.path().join("plugin");
+        let plugin = spec(&format!(r#"{{ url = "{url}", tag = "v1.0.0" }}"#));
+
+        let git = Git::default();
+        plugin
+            .try_install(&git, &destination, 1, &mut |_| {})
+            .unwrap();
+        plugin
+            .choose_locked_version(&git, &destination, &commits[1])
+            .unwrap();
+
+        let repo = git.for_repo(&destination);
+        assert_eq!(
+            crate::test_util::git(&destination, &["branch", "--show-current"]),
+            ""
+        );
+        assert_eq!(repo.current_commit().unwrap(), commits[1]);
+    }
+
+    #[test]
+    fn expand_shorthand_urls() {
+        assert_eq!(
+            expand_shorthand("tmux-plugins/tmux-yank", "github.com").as_deref(),
+            Some("https://github.com/tmux-plugins/tmux-yank")
+        );
+        assert_eq!(
+            expand_shorthand("owner/repo", "http://git.example.com/").as_deref(),
+            Some("http://git.example.com/owner/repo")
+        );
+        assert_eq!(
+            expand_shorthand("https://github.com/owner/repo", "github.com"),
+            None
+        );
+        assert_eq!(
+            expand_shorthand("git@github.com:owner/repo", "github.com"),
+            None
+        );
+        assert_eq!(expand_shorthand("owner/group/repo", "github.com"), None);
+        assert_eq!(expand_shorthand("repo", "github.com"), None);
+        assert_eq!(expand_shorthand("/repo", "github.com"), None);
+    }
+
+    #[test]
+    fn valid_url_shapes() {
+        let valid = [
+            "https://github.com/owner/repo",
+            "http://git.example.com/owner/repo.git",
+            "ssh://git@github.com:22/owner/repo.git",
+            "git://git.example.com/repo",
+            "git@github.com:owner/repo.git",
+        ];
+        let invalid = [
+            "owner/repo",
+            "ftp://example.com/repo",
+            "https:///owner/repo",
+            "github.com:owner/repo",
+            "git@github.com:",
+            "https://git hub.com/owner/repo",
+        ];
+
+        for url in valid {
+            assert!(is_valid_url(url), "{url}");
+        }
+        for url in invalid {
+            assert!(!is_valid_url(url), "{url}");
+        }
+    }
+
+    #[test]
+    fn sanitize_dir_name_keeps_names_inside_plugins_dir() {
+        assert_eq!(sanitize_dir_name("tmux-yank"), "tmux-yank");
+        assert_eq!(sanitize_dir_name("owner/repo"), "owner_repo");
+        assert_eq!(sanitize_dir_name("../escape"), ".._escape");
+        assert_eq!(sanitize_dir_name("my plugin"), "my_plugin");
+        assert_eq!(sanitize_dir_name(".."), "__");
+        assert_eq!(sanitize_dir_name("  "), "_");
+    }
+
+    /// Creates an executable script at `path`, creating missing parent directories
+    fn write_script(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn only_tmux_files_in_plugin_root_are_run() {
+        let dir = TempDir::new();
+        let plugin_dir = dir.path();
+        write_script(&plugin_dir.join("yank.tmux"));
+        write_script(&plugin_dir.join(".hidden.tmux"));
+        write_script(&plugin_dir.join("tests/foo.tmux"));
+        write_script(&plugin_dir.join(".git/hooks/hook.tmux"));
+        fs::write(plugin_dir.join("not-executable.tmux"), "").unwrap();
+        fs::write(plugin_dir.join("README.md"), "").unwrap();
+
+        let runnable = |require_executable| {
+            let mut files: Vec<_> = fs::read_dir(plugin_dir)
+                .unwrap()
+                .flatten()
+                .filter(|entry| is_runnable_tmux_file(entry, require_executable))
+                .map(|entry| entry.file_name())
+                .collect();
+            files.sort();
+            files
+        };
 
-        Ok(version.clone())
+        assert_eq!(runnable(true), ["yank.tmux"]);
+        assert_eq!(runnable(false), ["not-executable.tmux", "yank.tmux"]);
     }
 }
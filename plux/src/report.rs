@@ -0,0 +1,133 @@
+//! Structured results of processing plugins
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use crate::state::RunState;
+
+/// Outcome of processing every plugin in the spec, e.g. installing or sourcing them. Collected
+/// from all worker threads so it can be rendered in one piece once they are done.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InstallReport {
+    /// Plugins that were processed, with a short description, e.g. the installed version.
+    pub installed: Vec<(String, String)>,
+    /// Plugins that did not need processing, with the reason.
+    pub skipped: Vec<(String, String)>,
+    /// Plugins that failed, with the error message.
+    pub failed: Vec<(String, String)>,
+    /// Plugins that were cloned, with how long that took and how large they are.
+    pub clone_stats: Vec<(String, CloneStats)>,
+}
+
+/// Measurements of cloning a single plugin, to spot slow or large plugins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CloneStats {
+    /// Time it took to clone the plugin.
+    pub duration: Duration,
+    /// Size of the plugin directory after cloning it.
+    pub size_bytes: u64,
+}
+
+impl InstallReport {
+    /// Sorts all entries by plugin name, so the report doesn't depend on thread scheduling.
+    pub fn sort(&mut self) {
+        self.installed.sort();
+        self.skipped.sort();
+        self.failed.sort();
+        self.clone_stats.sort();
+    }
+
+    /// Checks whether processing any plugin failed.
+    pub fn has_failures(&self) -> bool {
+        !self.failed.is_empty()
+    }
+
+    /// Returns the clone measurements of the given plugin, if it was cloned.
+    pub fn clone_stats(&self, plugin: &str) -> Option<&CloneStats> {
+        self.clone_stats
+            .iter()
+            .find(|(name, _)| name == plugin)
+            .map(|(_, stats)| stats)
+    }
+
+    /// Converts the report into JSON, as printed with `--json`: a list of objects with the
+    /// plugin name and its description, skip reason or error for every outcome.
+    pub fn to_json(&self) -> serde_json::Value {
+        let entries = |entries: &[(String, String)], key: &str| {
+            entries
+                .iter()
+                .map(|(plugin, message)| {
+                    let mut entry = serde_json::Map::new();
+                    entry.insert("plugin".into(), plugin.as_str().into());
+                    entry.insert(key.into(), message.as_str().into());
+                    serde_json::Value::Object(entry)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        serde_json::json!({
+            "installed": entries(&self.installed, "detail"),
+            "skipped": entries(&self.skipped, "reason"),
+            "failed": entries(&self.failed, "error"),
+        })
+    }
+
+    /// Returns the run state to persist for this install report and the report of sourcing the
+    /// plugins afterwards. Skipped plugins were already installed, plugins that failed to install
+    /// or to load count as failed.
+    pub fn run_state(&self, source: &InstallReport) -> RunState {
+        let failed: BTreeSet<&str> = self
+            .failed
+            .iter()
+            .chain(&source.failed)
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        let installed = self
+            .installed
+            .iter()
+            .chain(&self.skipped)
+            .filter(|(name, _)| !failed.contains(name.as_str()))
+            .count();
+
+        RunState {
+            installed,
+            failed: failed.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(plugins: &[&str]) -> Vec<(String, String)> {
+        plugins
+            .iter()
+            .map(|plugin| (plugin.to_string(), String::new()))
+            .collect()
+    }
+
+    #[test]
+    fn run_state_counts_load_failures() {
+        let install = InstallReport {
+            installed: entries(&["a", "b"]),
+            skipped: entries(&["c"]),
+            failed: entries(&["d"]),
+            ..Default::default()
+        };
+        let source = InstallReport {
+            installed: entries(&["a", "c"]),
+            failed: entries(&["b", "d"]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            install.run_state(&source),
+            RunState {
+                installed: 2,
+                failed: 2,
+            }
+        );
+    }
+}
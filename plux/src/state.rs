@@ -0,0 +1,97 @@
+//! Persisted state of the last plux run
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::atomic_write;
+use crate::error::PluxError;
+
+/// Name of the state file, stored inside the plugins directory.
+pub const STATE_FILE_NAME: &str = ".plux-state.toml";
+
+/// Outcome of the last plux run. Persisted so that `plux status` can report plugin health
+/// without resolving anything through git.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RunState {
+    /// Number of plugins that are installed and were loaded.
+    pub installed: usize,
+    /// Number of plugins that failed to install or load.
+    pub failed: usize,
+}
+
+impl RunState {
+    /// Returns the path of the state file for the given plugins directory.
+    pub fn path(plugins_path: &Path) -> PathBuf {
+        plugins_path.join(STATE_FILE_NAME)
+    }
+
+    /// Loads the state of the last run, if any was persisted.
+    pub fn load(plugins_path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(Self::path(plugins_path)).ok()?;
+        toml::from_str(&contents).ok()
+    }
+
+    /// Persists the state into the given plugins directory.
+    pub fn save(&self, plugins_path: &Path) -> Result<(), PluxError> {
+        let path = Self::path(plugins_path);
+        let contents = toml::to_string(self)?;
+
+        atomic_write(&path, contents).map_err(|e| PluxError::StateWrite { path, source: e })
+    }
+
+    /// Renders a compact, single-line summary suitable for the tmux status bar, e.g. `✓10` when
+    /// all plugins are installed or `!2` when two plugins failed.
+    pub fn render_status(&self) -> String {
+        if self.failed > 0 {
+            format!("!{}", self.failed)
+        } else {
+            format!("✓{}", self.installed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn render_status_of_healthy_run() {
+        let state = RunState {
+            installed: 10,
+            failed: 0,
+        };
+
+        assert_eq!(state.render_status(), "✓10");
+    }
+
+    #[test]
+    fn render_status_of_failed_run() {
+        let state = RunState {
+            installed: 8,
+            failed: 2,
+        };
+
+        assert_eq!(state.render_status(), "!2");
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = TempDir::new();
+        let state = RunState {
+            installed: 3,
+            failed: 1,
+        };
+
+        state.save(dir.path()).unwrap();
+
+        assert_eq!(RunState::load(dir.path()), Some(state));
+    }
+
+    #[test]
+    fn load_without_state_file() {
+        let dir = TempDir::new();
+
+        assert_eq!(RunState::load(dir.path()), None);
+    }
+}
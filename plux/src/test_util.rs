@@ -0,0 +1,97 @@
+//! Helpers shared by the unit tests
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::Config;
+use crate::plugin::{DEFAULT_HOST, Layout, SourceMode};
+
+/// Directory in the system's temporary directory, removed with everything in it when dropped
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let name = format!(
+            "plux-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&path).unwrap();
+
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Runs git in `dir`, panicking if it fails, and returns its trimmed stdout
+pub(crate) fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(["-c", "user.name=plux", "-c", "user.email=plux@example.com"])
+        .args([
+            "-c",
+            "init.defaultBranch=main",
+            "-c",
+            "commit.gpgSign=false",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "git {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Creates a repository in `dir`, creating the directory if needed, with a single commit on
+/// `main`
+pub(crate) fn init_repo(dir: &Path) {
+    std::fs::create_dir_all(dir).unwrap();
+    git(dir, &["init", "--quiet"]);
+    git(
+        dir,
+        &["commit", "--quiet", "--allow-empty", "-m", "initial"],
+    );
+}
+
+/// Creates a configuration with the given TOML spec written to `plux.toml` in `dir` and the plugins
+/// directory `plugins` next to it
+pub(crate) fn config(dir: &Path, spec: &str) -> Config {
+    let spec_path = dir.join("plux.toml");
+    std::fs::write(&spec_path, spec).unwrap();
+
+    Config {
+        spec_path,
+        plugins_path: dir.join("plugins"),
+        layout: Layout::Flat,
+        clone_retries: 1,
+        clone_timeout: None,
+        max_parallel: 1,
+        auto_remove_orphans: false,
+        default_host: DEFAULT_HOST.to_string(),
+        git_path: None,
+        default_source_mode: SourceMode::Auto,
+        spec: toml::from_str(spec).unwrap(),
+        plugin_sources: HashMap::new(),
+        spec_modified: HashMap::new(),
+    }
+}
@@ -0,0 +1,90 @@
+//! Verification of installed plugins against the plugin spec
+
+use std::path::Path;
+
+use crate::git::{Git, GitError};
+use crate::plugin::{PluginSpec, Version};
+
+/// Result of comparing the checked out version of a plugin with its specification
+#[derive(Debug)]
+pub enum Verification {
+    /// The checked out version matches the specification.
+    Matches,
+    /// The plugin is not installed.
+    NotInstalled,
+    /// A different version than the one requested by the specification is checked out.
+    Drifted { expected: String, actual: String },
+    /// HEAD is detached, but the specification requests a branch.
+    Detached { expected: String },
+    /// The working tree has uncommitted changes.
+    Dirty,
+    /// The checked out version could not be determined.
+    Error(GitError),
+}
+
+impl Verification {
+    /// Checks whether the plugin is installed but checked out at the wrong version, which
+    /// choosing the version again can fix.
+    pub fn is_mismatch(&self) -> bool {
+        matches!(
+            self,
+            Verification::Drifted { .. } | Verification::Detached { .. }
+        )
+    }
+}
+
+/// Compares the version checked out in the plugin directory with the version requested by the
+/// specification. Plugins following a branch may also be detached at their locked commit.
+pub fn verify_plugin(
+    git: &Git,
+    plugin_dir: &Path,
+    spec: &PluginSpec,
+    locked_commit: Option<&str>,
+) -> Verification {
+    if !plugin_dir.is_dir() {
+        return Verification::NotInstalled;
+    }
+
+    check(&git.for_repo(plugin_dir), spec, locked_commit).unwrap_or_else(Verification::Error)
+}
+
+fn check(
+    git: &Git,
+    spec: &PluginSpec,
+    locked_commit: Option<&str>,
+) -> Result<Verification, GitError> {
+    if git.is_dirty()? {
+        return Ok(Verification::Dirty);
+    }
+
+    let head = git.current_commit()?;
+
+    let expected_branch = match spec.version() {
+        Some(version @ (Version::Tag(revision) | Version::Commit(revision))) => {
+            if git.resolve_commit(revision)? == head {
+                return Ok(Verification::Matches);
+            }
+
+            return Ok(Verification::Drifted {
+                expected: version.to_string(),
+                actual: git.current_ref()?,
+            });
+        }
+        Some(Version::Branch(branch)) => branch.trim().to_string(),
+        None => git.get_default_branch()?,
+    };
+
+    let verification = match git.current_branch()? {
+        Some(branch) if branch == expected_branch => Verification::Matches,
+        Some(branch) => Verification::Drifted {
+            expected: format!("branch '{expected_branch}'"),
+            actual: format!("branch '{branch}'"),
+        },
+        None if locked_commit.is_some_and(|commit| commit.trim() == head) => Verification::Matches,
+        None => Verification::Detached {
+            expected: format!("branch '{expected_branch}'"),
+        },
+    };
+
+    Ok(verification)
+}